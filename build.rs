@@ -1,34 +1,325 @@
-use cc;
+//! Compiles the Simulink controllers and generates their Rust bindings
+//!
+//! A Simulink controller is any directory below `src/controllers` with a header `<controller>.h` that defines the `ExtU_<controller>_T` structure.
+//! The C files of the directory are compiled into the library `<path>` where `<path>` is the directory path relative to `src/controllers` with `/` replaced by `_`, e.g. `mount_pdr_drives`.
+//! The inputs (`ExtU_<controller>_T`), outputs (`ExtY_<controller>_T`) and constant parameters (`ConstP_<controller>_T`) of the controller
+//! are written as an `import_simulink!` call, and the block states (`DW_<controller>_T`) and signals (`B_<controller>_T`) as an `import_simulink_states!` call, in `$OUT_DIR/<directory>/import_simulink.rs` that the controller module includes.
+//! The controllers of a family are compiled only if the family feature is enabled (see [`FEATURES`]).
+//! The build fails if the sizes in the `build_inputs!` and `build_outputs!` calls of the controller module do not match the sizes of the Simulink inputs and outputs.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The controllers families directories and features, a directory belongs to the first family with a matching path prefix
+const FEATURES: [(&str, &str); 3] = [
+    ("mount/pdr", "mount-pdr"),
+    ("mount", "mount-baseline"),
+    ("m1", "m1"),
+];
+
+/// A C structure: the list of the fields names, types and number of elements
+type CStruct = Vec<(String, String, usize)>;
+
+/// Copies the Simulink generated C files of a controller into the build directory
+///
+/// The `const` qualifier of the constant parameters structure is removed so the parameters can be overwritten at runtime
+fn simulink_sources(dir: &Path) -> PathBuf {
+    println!("cargo:rerun-if-changed={}", dir.display());
+    let out_dir = Path::new(&env::var("OUT_DIR").unwrap()).join(dir);
+    fs::create_dir_all(&out_dir).unwrap();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        match path.extension().and_then(|x| x.to_str()) {
+            Some("c") | Some("h") => {
+                let source = fs::read_to_string(&path)
+                    .unwrap()
+                    .replace("const ConstP_", "ConstP_");
+                fs::write(out_dir.join(path.file_name().unwrap()), source).unwrap();
+            }
+            _ => (),
+        }
+    }
+    out_dir
+}
+
+/// Removes the C comments
+fn strip_comments(source: &str) -> String {
+    let mut code = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(k) = rest.find("/*") {
+        code.push_str(&rest[..k]);
+        rest = rest[k + 2..]
+            .find("*/")
+            .map_or("", |e| &rest[k + 2 + e + 2..]);
+    }
+    code.push_str(rest);
+    code.lines()
+        .map(|l| l.split("//").next().unwrap())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses the `typedef struct {...} <name>;` definitions of the headers of a directory
+fn c_structs(dir: &Path) -> BTreeMap<String, CStruct> {
+    let mut structs = BTreeMap::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|x| x.to_str()) != Some("h") {
+            continue;
+        }
+        let code = strip_comments(&fs::read_to_string(&path).unwrap());
+        let mut rest = code.as_str();
+        while let Some(k) = rest.find("typedef struct {") {
+            rest = &rest[k + 16..];
+            let e = rest.find('}').unwrap();
+            let fields = rest[..e]
+                .split(';')
+                .map(|x| x.split_whitespace().collect::<Vec<_>>())
+                .filter(|x| x.len() >= 2)
+                .map(|x| {
+                    let declarator = x[1..].concat();
+                    let mut parts = declarator.split('[');
+                    let name = parts.next().unwrap().to_owned();
+                    let n: usize = parts
+                        .map(|d| d.trim_end_matches(']').parse::<usize>().unwrap())
+                        .product();
+                    (name, x[0].to_owned(), n)
+                })
+                .collect();
+            rest = &rest[e + 1..];
+            let name = rest[..rest.find(';').unwrap()].trim().to_owned();
+            structs.insert(name, fields);
+        }
+    }
+    structs
+}
+
+/// Returns the number of `real_T` of a C type or `None` if the type is not made only of `real_T`
+fn n_real(c_type: &str, structs: &BTreeMap<String, CStruct>) -> Option<usize> {
+    match c_type {
+        "real_T" => Some(1),
+        _ => structs.get(c_type).and_then(|fields| {
+            fields
+                .iter()
+                .map(|(_, t, n)| n_real(t, structs).map(|x| x * n))
+                .sum()
+        }),
+    }
+}
+
+/// Returns the names and the sizes of the fields of the structure `name`
+fn ports(name: &str, structs: &BTreeMap<String, CStruct>) -> Option<Vec<(String, usize)>> {
+    structs.get(name).map(|fields| {
+        fields
+            .iter()
+            .map(|(field, t, n)| {
+                let size = n_real(t, structs).unwrap_or_else(|| {
+                    panic!(
+                        "{}: field {} of type {} is not made of real_T",
+                        name, field, t
+                    )
+                });
+                (field.clone(), size * n)
+            })
+            .collect()
+    })
+}
+
+/// Returns the arguments of the first call to the macro `name` in a Rust source, without whitespaces
+fn macro_call(source: &str, name: &str) -> Option<String> {
+    let k = source.find(&format!("\n{}!(", name))? + name.len() + 3;
+    let e = source[k..].find(");")?;
+    Some(source[k..k + e].split_whitespace().collect())
+}
+
+/// Parses the variants names and sizes of a `build_inputs!` or `build_outputs!` call
+fn rust_sizes(args: &str) -> BTreeMap<String, usize> {
+    let tokens: Vec<&str> = args.split(',').collect();
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.parse::<usize>().is_err())
+        .map(|(k, t)| (t.to_string(), tokens[k + 1].parse().unwrap()))
+        .collect()
+}
+
+/// Parses the `<Simulink name> -> (<variant>,<variable>)` pairs of a `build_controller!` section
+fn rust_ports(section: &str) -> Vec<(String, String)> {
+    section
+        .split("),")
+        .filter_map(|x| {
+            let mut parts = x.split("->(");
+            let sim = parts.next()?;
+            let variant = parts.next()?.split(',').next()?;
+            Some((sim.to_owned(), variant.to_owned()))
+        })
+        .collect()
+}
+
+/// Checks that the sizes of the controller module match the sizes of the Simulink inputs and outputs
+fn check_sizes(dir: &Path, u: &[(String, usize)], y: &[(String, usize)]) {
+    let module = dir.join("mod.rs");
+    let source = match fs::read_to_string(&module) {
+        Ok(source) => source,
+        Err(_) => return,
+    };
+    let controller = match macro_call(&source, "build_controller") {
+        Some(controller) => controller,
+        None => return,
+    };
+    let (u_section, y_section) = controller.split_at(controller.find("Y:(").unwrap());
+    let mut errors = vec![];
+    for (kind, sim, section, macro_name) in [
+        ("input", u, u_section, "build_inputs"),
+        ("output", y, y_section, "build_outputs"),
+    ]
+    .iter()
+    {
+        let sizes = rust_sizes(&macro_call(&source, macro_name).unwrap_or_default());
+        for (sim_name, variant) in rust_ports(section) {
+            let sim_name = sim_name.rsplit('(').next().unwrap();
+            match (
+                sim.iter().find(|(name, _)| name == sim_name),
+                sizes.get(&variant),
+            ) {
+                (Some((_, c_size)), Some(rs_size)) if c_size != rs_size => errors.push(format!(
+                    "{} {} has size {} in Rust and {} in Simulink",
+                    kind, variant, rs_size, c_size
+                )),
+                (None, _) => errors.push(format!("unknown Simulink {} {}", kind, sim_name)),
+                _ => (),
+            }
+        }
+    }
+    if !errors.is_empty() {
+        panic!("{}:\n  {}", module.display(), errors.join("\n  "));
+    }
+}
+
+/// Writes the `import_simulink!` call of a controller
+fn import_simulink(
+    dir: &Path,
+    out_dir: &Path,
+    controller: &str,
+    structs: &BTreeMap<String, CStruct>,
+) {
+    let list = |ports: &[(String, usize)]| {
+        ports
+            .iter()
+            .map(|(name, size)| format!("{},{}", name, size))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let u = ports(&format!("ExtU_{}_T", controller), structs).unwrap();
+    let y = ports(&format!("ExtY_{}_T", controller), structs)
+        .unwrap_or_else(|| panic!("ExtY_{}_T not found", controller));
+    check_sizes(dir, &u, &y);
+    let mut code = format!(
+        "crate::import_simulink!({}, U : ({}), Y : ({})",
+        controller,
+        list(&u),
+        list(&y)
+    );
+    let const_p = format!("ConstP_{}_T", controller);
+    if structs.contains_key(&const_p) {
+        match n_real(&const_p, structs) {
+            Some(_) => code.push_str(&format!(
+                ", P : ({})",
+                list(&ports(&const_p, structs).unwrap())
+            )),
+            None => println!(
+                "cargo:warning={}: {} is not made of real_T, the parameters are not imported",
+                controller, const_p
+            ),
+        }
+    }
+    code.push_str(");\n");
+    let states: String = ["DW", "B"]
+        .iter()
+        .filter_map(|section| {
+            let name = format!("{}_{}_T", section, controller);
+            let fields: Vec<(String, usize)> = structs
+                .get(&name)?
+                .iter()
+                .map(|(field, t, n)| n_real(t, structs).map(|size| (field.clone(), size * n)))
+                .take_while(|x| x.is_some())
+                .flatten()
+                .collect();
+            if fields.is_empty() {
+                None
+            } else {
+                Some(format!(", {} : ({})", section, list(&fields)))
+            }
+        })
+        .collect();
+    code.push_str(&format!(
+        "crate::import_simulink_states!({}{});\n",
+        controller, states
+    ));
+    fs::write(out_dir.join("import_simulink.rs"), code).unwrap();
+}
+
+/// Returns the directories of the Simulink controllers and the controllers names
+fn discover(dir: &Path, controllers: &mut Vec<(PathBuf, String)>) {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            discover(&path, controllers);
+        } else if path.extension().and_then(|x| x.to_str()) == Some("h") {
+            let name = path.file_stem().unwrap().to_str().unwrap();
+            let source = fs::read_to_string(&path).unwrap();
+            if source.contains(&format!("}} ExtU_{}_T;", name)) {
+                controllers.push((dir.to_path_buf(), name.to_owned()));
+            }
+        }
+    }
+}
+
+/// Returns true if the feature of the family of the controller directory `dir` is enabled
+fn is_enabled(dir: &Path) -> bool {
+    match FEATURES.iter().find(|(family, _)| dir.starts_with(family)) {
+        Some((_, feature)) => env::var_os(format!(
+            "CARGO_FEATURE_{}",
+            feature.to_uppercase().replace('-', "_")
+        ))
+        .is_some(),
+        None => true,
+    }
+}
 
 fn main() {
-    cc::Build::new()
-        .file("src/controllers/mount/controller/MountControl0.c")
-        .file("src/controllers/mount/controller/MountControl0_data.c")
-        .compile("mount_controller");
-    cc::Build::new()
-        .file("src/controllers/mount/pdr/controller/Mount_Control.c")
-        .file("src/controllers/mount/pdr/controller/Mount_Control_data.c")
-        .compile("mount_pdr_controller");
-    cc::Build::new()
-        .file("src/controllers/mount/drives/MountDrives.c")
-        .file("src/controllers/mount/drives/MountDrives_data.c")
-        .compile("mount_drives");
-    cc::Build::new()
-        .file("src/controllers/mount/pdr/drives/Mount_Drv_PDR2021.c")
-        .file("src/controllers/mount/pdr/drives/Mount_Drv_PDR2021_data.c")
-        .file("src/controllers/mount/pdr/drives/rtGetInf.c")
-        .file("src/controllers/mount/pdr/drives/rtGetNaN.c")
-        .file("src/controllers/mount/pdr/drives/rt_nonfinite.c")
-        .compile("mount_pdr_drives");
-    cc::Build::new()
-        .file("src/controllers/m1/hp_load_cells/M1HPloadcells.c")
-        .compile("m1_hp_load_cells");
-    cc::Build::new()
-        .file("src/controllers/m1/local_controller/M1LocalControl.c")
-        .file("src/controllers/m1/local_controller/M1LocalControl_data.c")
-        .compile("m1_local_controller");
-    cc::Build::new()
-        .file("src/controllers/m1/cg_controller/M1OFL_Control.c")
-        .file("src/controllers/m1/cg_controller/M1OFL_Control_data.c")
-        .compile("m1_cg_controller");
+    let root = Path::new("src").join("controllers");
+    let mut controllers = vec![];
+    discover(&root, &mut controllers);
+    for (dir, controller) in controllers {
+        if !is_enabled(dir.strip_prefix(&root).unwrap()) {
+            continue;
+        }
+        let out_dir = simulink_sources(&dir);
+        let structs = c_structs(&out_dir);
+        let mut build = cc::Build::new();
+        for entry in fs::read_dir(&out_dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|x| x.to_str()) == Some("c")
+                && path.file_name().and_then(|x| x.to_str()) != Some("ert_main.c")
+            {
+                build.file(path);
+            }
+        }
+        import_simulink(&dir, &out_dir, &controller, &structs);
+        let lib = dir
+            .strip_prefix(&root)
+            .unwrap()
+            .iter()
+            .map(|x| x.to_str().unwrap())
+            .collect::<Vec<_>>()
+            .join("_");
+        build.compile(&lib);
+    }
 }
@@ -1,5 +1,5 @@
 use dos::{
-    controllers::{m1, mount::pdr as mount, state_space::DiscreteStateSpace},
+    controllers::{m1, mount::system::MountSystem, state_space::DiscreteStateSpace},
     io::jar::*,
     io::IO,
     DataLogging, WindLoads, DOS,
@@ -43,7 +43,7 @@ fn job(cfd_case: &str) -> Result<(), Box<dyn Error>> {
     //let n_sample = 20 * 1000;
     let mut wind_loading =
         WindLoads::from_pickle(fem_data_path.join(cfd_case).join("wind_loads_2kHz.pkl"))?
-            .range(0.0, 400.0)
+            .range(0.0, 400.0)?
             .decimate(2)
             .truss()?
             .m2_asm_topend()?
@@ -53,12 +53,19 @@ fn job(cfd_case: &str) -> Result<(), Box<dyn Error>> {
             .build()?;
     tic.print_toc();
     // MOUNT CONTROL
-    let mut mnt_drives = mount::drives::Controller::new();
-    let mut mnt_ctrl = mount::controller::Controller::new();
+    let mount_model = std::env::var("MOUNT_MODEL").unwrap_or_else(|_| "pdr".to_owned());
+    let mut mount = MountSystem::new(mount_model.parse()?)?;
 
     // M1
     let mut m1_hardpoints = m1::hp_load_cells::Controller::new();
     let mut m1_ctrl = m1::cg_controller::Controller::new();
+    let m1_hp_schedule = match std::env::var("M1_HP_SCHEDULE") {
+        Ok(path) => m1::hardpoints::Schedule::from_pickle(path)?,
+        Err(_) => Default::default(),
+    };
+    let mut m1_hp_cmd = m1::hardpoints::HardpointsCommands::new(1e2)
+        .schedule(m1_hp_schedule)?
+        .build();
 
     // FEM
     let sampling_rate = 1e3;
@@ -74,13 +81,9 @@ fn job(cfd_case: &str) -> Result<(), Box<dyn Error>> {
     .proportional_damping(2. / 100.)
     .max_eigen_frequency(75.0)
     .inputs_from(&wind_loading)
-    .inputs_from(&mnt_drives)
+    .inputs_from(&mount)
     .outputs(vec![m1_rbm.clone(), m2_rbm.clone()])
-    .outputs(vec![
-        OSSAzEncoderAngle::new(),
-        OSSElEncoderAngle::new(),
-        OSSRotEncoderAngle::new(),
-    ])
+    .outputs_to(&mount)
     .outputs(vec![OSSHardpointD::new()])
     .build()?;
     tic.print_toc();
@@ -88,51 +91,41 @@ fn job(cfd_case: &str) -> Result<(), Box<dyn Error>> {
     // DATA LOGGING
     let mut data = DataLogging::new()
         .sampling_rate(sampling_rate)
-        //.key(m1_rbm.clone())
-        //.key(m2_rbm.clone())
+        .key(m1_rbm.clone())
+        .key(m2_rbm.clone())
         .build();
 
     println!("Running model ...");
     let tic = Timer::tic();
-    let mut mount_drives_forces = Some(vec![
-        OSSAzDriveTorque::with(vec![0f64; 12]),
-        OSSElDriveTorque::with(vec![0f64; 4]),
-        OSSRotDriveTorque::with(vec![0f64; 4]),
-    ]);
+    let mut mount_drives_forces = mount.outputs();
     let mut m1_cg_fm: Option<Vec<IO<Vec<f64>>>> = None;
     // FEEDBACK LOOP
     let mut k = 0;
     while let Some(mut fem_forces) = wind_loading.outputs() {
         // FEM
-        mount_drives_forces.as_mut().map(|x| {
+        if let Some(x) = mount_drives_forces.as_mut() {
             fem_forces.append(x);
-        });
-        m1_cg_fm.as_ref().map(|x| {
+        }
+        if let Some(x) = m1_cg_fm.as_ref() {
             fem_forces[OSSM1Lcl6F::new()] += &x[0];
             fem_forces[OSSCellLcl6F::new()] -= &x[0];
-        });
+        }
         let fem_outputs = fem.in_step_out(fem_forces)?.ok_or("FEM output is empty")?;
         // MOUNT CONTROLLER & DRIVES
-        let mount_encoders = &fem_outputs[2..5];
-        mount_drives_forces = mnt_ctrl
-            .in_step_out(mount_encoders.to_vec())?
-            .and_then(|mut x| {
-                x.extend_from_slice(mount_encoders);
-                Some(mnt_drives.in_step_out(x.to_owned()))
-            })
-            .unwrap()?;
+        mount_drives_forces = mount.in_step_out(fem_outputs[2..5].to_vec())?;
         // M1 HARDPOINT & CG CONTROLLER
         if k % 10 == 0 {
-            let mut m1_hp = vec![M1HPCmd::with(vec![0f64; 42])];
+            let mut m1_hp = m1_hp_cmd
+                .outputs()
+                .ok_or("M1 hardpoints commands are empty")?;
             m1_hp.extend_from_slice(&[fem_outputs[OSSHardpointD::new()].clone()]);
             m1_cg_fm = m1_hardpoints
                 .in_step_out(m1_hp)?
-                .and_then(|x| Some(m1_ctrl.in_step_out(x)))
+                .map(|x| m1_ctrl.in_step_out(x))
                 .unwrap()?;
         }
         // DATA LOGGING
-        data.step()?;
-        data.log(&fem_outputs[0])?.log(&fem_outputs[1])?;
+        data.inputs(fem_outputs.clone())?.step()?;
         k += 1;
     }
     tic.print_toc();
@@ -0,0 +1,98 @@
+//! Second order sections
+//!
+//! A [`Biquad`] is a second order transfer function
+//! ```math
+//! H(z) = {b_0 + b_1 z^{-1} + b_2 z^{-2} \over a_0 + a_1 z^{-1} + a_2 z^{-2}}
+//! ```
+//! and a [`Cascade`] is a series of biquads, usually obtained from the second order sections (SOS) of a higher order filter, e.g. with `scipy.signal.butter(..., output='sos')`.
+//!
+//! The notch and low-pass biquads follow the [Audio EQ Cookbook](https://www.w3.org/TR/audio-eq-cookbook/).
+
+use super::{Filter, LinearError, Result};
+use crate::DOSError;
+use std::f64::consts::PI;
+
+/// Second order transfer function
+#[derive(Debug, Clone, Default)]
+pub struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 2],
+}
+impl Biquad {
+    /// Creates a biquad from the numerator `b` and denominator `a` coefficients
+    pub fn new(b: [f64; 3], a: [f64; 3]) -> Result<Self> {
+        if a[0] == 0f64 {
+            return Err(DOSError::Component(LinearError::Denominator));
+        }
+        Ok(Self {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+            state: [0f64; 2],
+        })
+    }
+    /// Creates a notch filter at `frequency` in Hz with the quality factor `q`
+    ///
+    /// The width of the notch is `frequency/q`
+    pub fn notch(sampling_rate: f64, frequency: f64, q: f64) -> Result<Self> {
+        let w0 = 2. * PI * frequency / sampling_rate;
+        let alpha = w0.sin() / (2. * q);
+        let cos_w0 = w0.cos();
+        Self::new(
+            [1., -2. * cos_w0, 1.],
+            [1. + alpha, -2. * cos_w0, 1. - alpha],
+        )
+    }
+    /// Creates a 2nd order low-pass filter with the cut-off `frequency` in Hz and the quality factor `q`
+    ///
+    /// A Butterworth filter has $`q=1/\sqrt 2`$
+    pub fn lowpass(sampling_rate: f64, frequency: f64, q: f64) -> Result<Self> {
+        let w0 = 2. * PI * frequency / sampling_rate;
+        let alpha = w0.sin() / (2. * q);
+        let cos_w0 = w0.cos();
+        let b1 = 1. - cos_w0;
+        Self::new(
+            [0.5 * b1, b1, 0.5 * b1],
+            [1. + alpha, -2. * cos_w0, 1. - alpha],
+        )
+    }
+}
+impl Filter for Biquad {
+    fn update(&mut self, u: f64) -> f64 {
+        let y = self.b[0] * u + self.state[0];
+        self.state[0] = self.b[1] * u - self.a[0] * y + self.state[1];
+        self.state[1] = self.b[2] * u - self.a[1] * y;
+        y
+    }
+    fn reset(&mut self) {
+        self.state = [0f64; 2];
+    }
+}
+
+/// Series of biquads
+#[derive(Debug, Clone, Default)]
+pub struct Cascade {
+    pub sections: Vec<Biquad>,
+}
+impl Cascade {
+    /// Creates a cascade of biquads
+    pub fn new(sections: Vec<Biquad>) -> Self {
+        Self { sections }
+    }
+    /// Creates a cascade from second order sections `[b0, b1, b2, a0, a1, a2]`
+    pub fn from_sos(sos: &[[f64; 6]]) -> Result<Self> {
+        Ok(Self::new(
+            sos.iter()
+                .map(|s| Biquad::new([s[0], s[1], s[2]], [s[3], s[4], s[5]]))
+                .collect::<Result<Vec<Biquad>>>()?,
+        ))
+    }
+}
+impl Filter for Cascade {
+    fn update(&mut self, u: f64) -> f64 {
+        self.sections.iter_mut().fold(u, |u, s| s.update(u))
+    }
+    fn reset(&mut self) {
+        self.sections.iter_mut().for_each(|s| s.reset());
+    }
+}
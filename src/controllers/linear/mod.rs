@@ -0,0 +1,249 @@
+//! Native Rust linear controllers
+//!
+//! This module is used to prototype control loops without generating C code from Simulink.
+//!
+//! The single input single output controllers implement the [`Filter`] trait:
+//!  - [`TransferFunction`] for any discrete transfer function and for the lead-lag compensators,
+//!  - [`Biquad`] for second order sections and for the notch filters, and [`Cascade`] for a series of biquads,
+//!  - [`Pid`] for the PID controller with anti-windup.
+//!
+//! A filter becomes a DOS component with [`LinearController`] that applies the same filter independently to each element of an input.
+//!
+//! The multiple inputs multiple outputs [`StateSpace`] controller is a DOS component with the A, B, C and D matrices loaded from a pickle file or a NumPy archive.
+//!
+//! # Example
+//! A PID controller of the mount elevation drive followed by a notch filter at 3.5Hz
+//! ```no_run
+//! use dos::{
+//!     controllers::linear::{Biquad, Cascade, LinearController, Pid, StateSpace},
+//!     io::jar,
+//!     DOS,
+//! };
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let sampling_rate = 1e3;
+//!     let mut pid = LinearController::new(
+//!         Pid::new(sampling_rate, 1e3, 1e2, 0.).saturation(-1e6, 1e6),
+//!         jar::OSSElEncoderAngle::new(),
+//!         jar::MountCmd::new(),
+//!     );
+//!     let mut notch = LinearController::new(
+//!         Cascade::new(vec![Biquad::notch(sampling_rate, 3.5, 5.)?]),
+//!         jar::MountCmd::new(),
+//!         jar::MountCmd::new(),
+//!     );
+//!     let mut mount_ctrl = StateSpace::from_file("mount_controller.npz")?
+//!         .inputs(vec![
+//!             jar::OSSAzEncoderAngle::new(),
+//!             jar::OSSElEncoderAngle::new(),
+//!             jar::OSSRotEncoderAngle::new(),
+//!         ])
+//!         .outputs(vec![jar::MountCmd::with(3)])?;
+//!     // ...
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    io::{IOError, Tags},
+    DOSError, IOTags, DOS, IO,
+};
+
+pub mod biquad;
+#[doc(inline)]
+pub use biquad::{Biquad, Cascade};
+pub mod pid;
+#[doc(inline)]
+pub use pid::Pid;
+pub mod state_space;
+#[doc(inline)]
+pub use state_space::StateSpace;
+pub mod transfer_function;
+#[doc(inline)]
+pub use transfer_function::TransferFunction;
+
+#[derive(Debug)]
+pub enum LinearError {
+    /// Empty denominator or leading denominator coefficient equal to 0
+    Denominator,
+    /// Matrices or inputs of inconsistent sizes
+    Size(String),
+    /// Matrix missing from the data file
+    Missing(String),
+    /// Unsupported data file format or invalid data file
+    Format(String),
+    /// Input missing from the inputs data
+    Input(Tags),
+}
+type Result<T> = std::result::Result<T, DOSError<LinearError>>;
+
+/// Single input single output discrete linear controller
+pub trait Filter {
+    /// Updates the controller with the input `u` and returns the controller output
+    fn update(&mut self, u: f64) -> f64;
+    /// Sets the controller state to 0
+    fn reset(&mut self);
+}
+
+/// Single input single output controller component
+///
+/// A copy of the filter is applied to each element of the input, the filters are created at the first time step
+pub struct LinearController<F> {
+    filter: F,
+    filters: Vec<F>,
+    u: Vec<f64>,
+    y: Vec<f64>,
+    u_tag: Tags,
+    y_tag: Tags,
+}
+impl<F: Filter + Clone> LinearController<F> {
+    /// Creates a controller component from a filter and the input and output tags
+    pub fn new(filter: F, input: Tags, output: Tags) -> Self {
+        Self {
+            filter,
+            filters: vec![],
+            u: vec![],
+            y: vec![],
+            u_tag: input,
+            y_tag: output,
+        }
+    }
+    /// Returns the filters, one per input element
+    pub fn filters(&self) -> &[F] {
+        &self.filters
+    }
+}
+impl<F: Filter + Clone> Iterator for LinearController<F> {
+    type Item = ();
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.filters.len() != self.u.len() {
+            self.filters = vec![self.filter.clone(); self.u.len()];
+        }
+        self.y = self
+            .filters
+            .iter_mut()
+            .zip(self.u.iter())
+            .map(|(f, u)| f.update(*u))
+            .collect();
+        Some(())
+    }
+}
+impl<F> IOTags for LinearController<F> {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        vec![self.y_tag.clone()]
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        vec![self.u_tag.clone()]
+    }
+}
+impl<F: Filter + Clone> DOS for LinearController<F> {
+    fn inputs(
+        &mut self,
+        data: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        let u = data
+            .into_iter()
+            .find(|x| *x == self.u_tag)
+            .ok_or_else(|| DOSError::Component(LinearError::Input(self.u_tag.clone())))?;
+        self.u = std::result::Result::<Vec<f64>, DOSError<IOError>>::from(u)?;
+        Ok(self)
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        Some(vec![IO::from((&self.y_tag, self.y.clone()))])
+    }
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        self.filters.iter_mut().for_each(|f| f.reset());
+        self.u.clear();
+        self.y.clear();
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::jar;
+
+    #[test]
+    fn transfer_functions() {
+        // integrator
+        let mut tf = TransferFunction::new(vec![0., 1.], vec![1., -1.]).unwrap();
+        let y: Vec<f64> = (0..4).map(|_| tf.update(1.)).collect();
+        assert_eq!(y, vec![0., 1., 2., 3.]);
+        // the biquad and the transfer function with the same coefficients are identical
+        let mut biquad = Biquad::new([1., 0.5, 0.25], [2., -0.4, 0.1]).unwrap();
+        let mut tf = TransferFunction::new(vec![1., 0.5, 0.25], vec![2., -0.4, 0.1]).unwrap();
+        assert!((0..100)
+            .map(|k| (2. * std::f64::consts::PI * 0.05 * k as f64).sin())
+            .all(|u| (biquad.update(u) - tf.update(u)).abs() < 1e-12));
+        // the lead-lag static gain
+        let mut lead = TransferFunction::lead_lag(1e3, 2., 1., 10.).unwrap();
+        let y = (0..10_000).fold(0., |_, _| lead.update(1.));
+        assert!((y - 2.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn biquads() {
+        use std::f64::consts::PI;
+        // the notch steady state gain is 0 at the notch frequency and 1 at DC
+        let mut notch = Biquad::notch(1e3, 50., 5.).unwrap();
+        let y: Vec<f64> = (0..2_000)
+            .map(|k| notch.update((2. * PI * 50. * k as f64 * 1e-3).sin()))
+            .collect();
+        assert!(y[1_900..].iter().all(|y| y.abs() < 1e-9));
+        notch.reset();
+        let y = (0..2_000).fold(0., |_, _| notch.update(1.));
+        assert!((y - 1.).abs() < 1e-9);
+        // the low-pass DC gain is 1
+        let mut lowpass = Biquad::lowpass(1e3, 50., 0.5f64.sqrt()).unwrap();
+        let y = (0..2_000).fold(0., |_, _| lowpass.update(1.));
+        assert!((y - 1.).abs() < 1e-9);
+        // the second order sections are [b0, b1, b2, a0, a1, a2] and are applied one after the other,
+        // i.e. the cascade is the transfer function with the products of the numerators and of the denominators
+        let mut cascade =
+            Cascade::from_sos(&[[1., 0.5, 0.25, 2., -0.4, 0.1], [0.5, 0., -0.5, 1., 0.2, 0.]])
+                .unwrap();
+        assert_eq!(cascade.sections.len(), 2);
+        let mut tf = TransferFunction::new(
+            vec![0.5, 0.25, -0.375, -0.25, -0.125],
+            vec![2., 0., 0.02, 0.02, 0.],
+        )
+        .unwrap();
+        assert!((0..100)
+            .map(|k| (2. * PI * 0.05 * k as f64).sin())
+            .all(|u| (cascade.update(u) - tf.update(u)).abs() < 1e-12));
+        assert!(Cascade::from_sos(&[[1., 0., 0., 0., 1., 0.]]).is_err());
+    }
+
+    #[test]
+    fn pid_derivative_filter() {
+        // first order low-pass filter of the derivative with the weight 1/2
+        let mut pid = Pid::new(1., 0., 0., 1.).derivative_filter(0.5 / std::f64::consts::PI);
+        let y: Vec<f64> = (0..3).map(|_| pid.update(1.)).collect();
+        assert!(y
+            .iter()
+            .zip(&[0.5, 0.25, 0.125])
+            .all(|(y, e)| (y - e).abs() < 1e-12));
+    }
+
+    #[test]
+    fn pid_anti_windup() {
+        let mut pid = LinearController::new(
+            Pid::new(1., 0., 1., 0.).saturation(-1., 1.),
+            jar::OSSElEncoderAngle::new(),
+            jar::MountCmd::new(),
+        );
+        let mut y = vec![];
+        for e in [1., 1., 1., -1.].iter() {
+            let cmd = pid
+                .inputs(vec![jar::OSSElEncoderAngle::with(vec![*e, 0.])])
+                .unwrap()
+                .step()
+                .unwrap()
+                .outputs()
+                .unwrap();
+            y.push(Option::<Vec<f64>>::from(&cmd[0]).unwrap()[0]);
+        }
+        assert_eq!(y, vec![1., 1., 1., 0.]);
+    }
+}
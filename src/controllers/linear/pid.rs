@@ -0,0 +1,89 @@
+//! PID controller with anti-windup
+//!
+//! The controller output is
+//! ```math
+//! y\[k\] = K_p e\[k\] + K_i \tau \sum_{j\le k} e\[j\] + K_d {e\[k\]-e\[k-1\] \over \tau}
+//! ```
+//! where $`e`$ is the error, i.e. the controller input, and $`\tau`$ the sampling time.
+//! The derivative term is optionally low-pass filtered.
+//!
+//! If the output is saturated, the integrator is frozen as long as the error drives the output further into saturation (conditional integration).
+
+use super::Filter;
+use std::f64::consts::PI;
+
+/// PID controller
+#[derive(Debug, Clone, Default)]
+pub struct Pid {
+    kp: f64,
+    ki: f64,
+    kd: f64,
+    tau: f64,
+    derivative_weight: Option<f64>,
+    saturation: Option<(f64, f64)>,
+    integral: f64,
+    derivative: f64,
+    error: f64,
+}
+impl Pid {
+    /// Creates a PID controller with the proportional `kp`, integral `ki` and derivative `kd` gains
+    pub fn new(sampling_rate: f64, kp: f64, ki: f64, kd: f64) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            tau: sampling_rate.recip(),
+            ..Default::default()
+        }
+    }
+    /// Low-pass filters the derivative term with a first order filter of cut-off `frequency` in Hz
+    pub fn derivative_filter(self, frequency: f64) -> Self {
+        let wt = 2. * PI * frequency * self.tau;
+        Self {
+            derivative_weight: Some(wt / (1. + wt)),
+            ..self
+        }
+    }
+    /// Clamps the controller output between `min` and `max`
+    pub fn saturation(self, min: f64, max: f64) -> Self {
+        Self {
+            saturation: Some((min, max)),
+            ..self
+        }
+    }
+    /// Returns the value of the integral term
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+}
+impl Filter for Pid {
+    fn update(&mut self, e: f64) -> f64 {
+        let p = self.kp * e;
+        let d = self.kd * (e - self.error) / self.tau;
+        self.error = e;
+        self.derivative = match self.derivative_weight {
+            Some(w) => self.derivative + w * (d - self.derivative),
+            None => d,
+        };
+        let integral = self.integral + self.ki * self.tau * e;
+        let y = p + integral + self.derivative;
+        match self.saturation {
+            Some((min, max)) => {
+                let y_sat = y.max(min).min(max);
+                if y_sat == y || e * (y - y_sat) < 0f64 {
+                    self.integral = integral;
+                }
+                (p + self.integral + self.derivative).max(min).min(max)
+            }
+            None => {
+                self.integral = integral;
+                y
+            }
+        }
+    }
+    fn reset(&mut self) {
+        self.integral = 0f64;
+        self.derivative = 0f64;
+        self.error = 0f64;
+    }
+}
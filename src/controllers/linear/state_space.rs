@@ -0,0 +1,315 @@
+//! Generic discrete state space controller
+//!
+//! The controller is the multiple inputs multiple outputs model
+//! $$
+//! x[k+1] = A x\[k\] + B u\[k\]
+//! $$
+//! $$
+//! y\[k\] = C x\[k\] + D u\[k\]
+//! $$
+//! The matrices are loaded either from a pickle file or from a NumPy archive (see [`StateSpace::from_file`]).
+
+use super::{LinearError, Result};
+use crate::{
+    formats::read_npy,
+    io::{IOError, Tags},
+    DOSError, IOTags, DOS, IO,
+};
+use nalgebra::{DMatrix, DVector};
+use serde::Deserialize;
+use serde_pickle as pkl;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// State space matrices in a pickle file, a dictionary of list of rows
+#[derive(Deserialize)]
+struct Matrices {
+    #[serde(rename = "A")]
+    a: Vec<Vec<f64>>,
+    #[serde(rename = "B")]
+    b: Vec<Vec<f64>>,
+    #[serde(rename = "C")]
+    c: Vec<Vec<f64>>,
+    #[serde(rename = "D")]
+    d: Option<Vec<Vec<f64>>>,
+}
+
+/// Discrete state space controller
+#[derive(Debug, Clone)]
+pub struct StateSpace {
+    a: DMatrix<f64>,
+    b: DMatrix<f64>,
+    c: DMatrix<f64>,
+    d: DMatrix<f64>,
+    x: DVector<f64>,
+    u: Vec<f64>,
+    y: Vec<f64>,
+    u_tags: Vec<Tags>,
+    y_tags: Vec<IO<usize>>,
+}
+impl StateSpace {
+    /// Creates a state space controller from the A, B, C and D matrices, each given as a list of rows
+    ///
+    /// An empty D matrix is set to 0
+    pub fn new(
+        a: Vec<Vec<f64>>,
+        b: Vec<Vec<f64>>,
+        c: Vec<Vec<f64>>,
+        d: Vec<Vec<f64>>,
+    ) -> Result<Self> {
+        let n_x = a.len();
+        let n_u = b.first().or_else(|| d.first()).map_or(0, |x| x.len());
+        let n_y = if c.is_empty() { d.len() } else { c.len() };
+        let matrix = |name: &str, m: Vec<Vec<f64>>, n_row: usize, n_col: usize| {
+            if m.len() != n_row || m.iter().any(|x| x.len() != n_col) {
+                return Err(DOSError::Component(LinearError::Size(format!(
+                    "{} is not {}x{}",
+                    name, n_row, n_col
+                ))));
+            }
+            Ok(DMatrix::from_row_slice(
+                n_row,
+                n_col,
+                &m.into_iter().flatten().collect::<Vec<f64>>(),
+            ))
+        };
+        let d = if d.is_empty() {
+            DMatrix::zeros(n_y, n_u)
+        } else {
+            matrix("D", d, n_y, n_u)?
+        };
+        Ok(Self {
+            a: matrix("A", a, n_x, n_x)?,
+            b: matrix("B", b, n_x, n_u)?,
+            c: matrix("C", c, n_y, n_x)?,
+            d,
+            x: DVector::zeros(n_x),
+            u: vec![0f64; n_u],
+            y: vec![0f64; n_y],
+            u_tags: vec![],
+            y_tags: vec![],
+        })
+    }
+    /// Reads the matrices from a data file, the file format is given by the file extension
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match path.extension().and_then(|x| x.to_str()) {
+            Some("pkl") | Some("pickle") => Self::from_pickle(path),
+            Some("npz") => Self::from_npz(path),
+            _ => Err(DOSError::Component(LinearError::Format(format!(
+                "unknown file format: {:?}",
+                path
+            )))),
+        }
+    }
+    /// Reads the matrices from a pickle file
+    ///
+    /// In Python, the matrices are saved as the dictionary `{'A': [[...]], 'B': [[...]], 'C': [[...]], 'D': [[...]]}`, `D` is optional
+    pub fn from_pickle<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = File::open(path)?;
+        let m: Matrices = pkl::from_reader(BufReader::new(f))?;
+        Self::new(m.a, m.b, m.c, m.d.unwrap_or_default())
+    }
+    /// Reads the matrices from a NumPy archive
+    ///
+    /// In Python, the archive is saved with `numpy.savez('controller.npz', A=A, B=B, C=C, D=D)`, `D` is optional
+    pub fn from_npz<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = File::open(path)?;
+        let mut archive = zip::ZipArchive::new(BufReader::new(f))?;
+        let mut matrices = BTreeMap::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let name = file.name().trim_end_matches(".npy").to_owned();
+            let mut buffer = vec![];
+            file.read_to_end(&mut buffer)?;
+            let (shape, values) = read_npy(&buffer).map_err(|e| {
+                DOSError::Component(LinearError::Format(format!("{}: {}", name, e)))
+            })?;
+            let n_col = match shape.len() {
+                2 => shape[1],
+                _ => values.len(),
+            };
+            let rows: Vec<Vec<f64>> = if n_col == 0 {
+                vec![vec![]; shape.first().cloned().unwrap_or(0)]
+            } else {
+                values.chunks(n_col).map(|x| x.to_vec()).collect()
+            };
+            matrices.insert(name, rows);
+        }
+        let mut take = |name: &str| {
+            matrices
+                .remove(name)
+                .ok_or_else(|| DOSError::Component(LinearError::Missing(name.to_owned())))
+        };
+        let (a, b, c) = (take("A")?, take("B")?, take("C")?);
+        Self::new(a, b, c, take("D").unwrap_or_default())
+    }
+    /// Sets the controller inputs, the inputs are concatenated in the given order
+    pub fn inputs(self, u_tags: Vec<Tags>) -> Self {
+        Self { u_tags, ..self }
+    }
+    /// Sets the controller outputs, each output is tagged with its size, e.g. `jar::MountCmd::with(3)`
+    ///
+    /// The sizes must sum to the number of rows of C
+    pub fn outputs(self, y_tags: Vec<IO<usize>>) -> Result<Self> {
+        let n: usize = y_tags
+            .iter()
+            .map(|x| Option::<usize>::from(x).unwrap_or(0))
+            .sum();
+        if n != self.y.len() {
+            return Err(DOSError::Component(LinearError::Size(format!(
+                "{} outputs for {} rows of C",
+                n,
+                self.y.len()
+            ))));
+        }
+        Ok(Self { y_tags, ..self })
+    }
+    /// Returns the state vector
+    pub fn state(&self) -> &[f64] {
+        self.x.as_slice()
+    }
+}
+impl Iterator for StateSpace {
+    type Item = ();
+    fn next(&mut self) -> Option<Self::Item> {
+        let u = DVector::from_column_slice(&self.u);
+        self.y = (&self.c * &self.x + &self.d * &u).as_slice().to_vec();
+        self.x = &self.a * &self.x + &self.b * u;
+        Some(())
+    }
+}
+impl IOTags for StateSpace {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        self.y_tags.iter().map(|x| x.into()).collect()
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        self.u_tags.clone()
+    }
+}
+impl DOS for StateSpace {
+    fn inputs(
+        &mut self,
+        mut data: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        let mut u = Vec::with_capacity(self.u.len());
+        for tag in &self.u_tags {
+            let k = data
+                .iter()
+                .position(|x| x == tag)
+                .ok_or_else(|| DOSError::Component(LinearError::Input(tag.clone())))?;
+            u.extend(std::result::Result::<Vec<f64>, DOSError<IOError>>::from(
+                data.remove(k),
+            )?);
+        }
+        if u.len() != self.u.len() {
+            return Err(DOSError::Component(LinearError::Size(format!(
+                "{} inputs for {} columns of B",
+                u.len(),
+                self.u.len()
+            )))
+            .into());
+        }
+        self.u = u;
+        Ok(self)
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        let mut pos = 0;
+        self.y_tags
+            .iter()
+            .map(|t| {
+                let n = Option::<usize>::from(t)?;
+                let io = IO::<Vec<f64>>::from((t, self.y[pos..pos + n].to_vec()));
+                pos += n;
+                Some(io)
+            })
+            .collect()
+    }
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        self.x.fill(0f64);
+        self.u.iter_mut().for_each(|u| *u = 0f64);
+        self.y.iter_mut().for_each(|y| *y = 0f64);
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::jar;
+    use std::io::Write;
+
+    fn matrices() -> Vec<(&'static str, Vec<Vec<f64>>)> {
+        vec![
+            ("A", vec![vec![0.5, 0.], vec![0.25, 0.5]]),
+            ("B", vec![vec![1.], vec![0.]]),
+            ("C", vec![vec![1., 0.], vec![0., 2.]]),
+            ("D", vec![vec![0.], vec![1.]]),
+        ]
+    }
+
+    fn run(ss: StateSpace) -> Vec<Vec<f64>> {
+        let mut ss = ss
+            .inputs(vec![jar::OSSElEncoderAngle::new()])
+            .outputs(vec![jar::MountCmd::with(1), jar::M1HPCmd::with(1)])
+            .unwrap();
+        assert_eq!(
+            ss.outputs_tags(),
+            vec![jar::MountCmd::new::<()>(), jar::M1HPCmd::new()]
+        );
+        [1., 2., -1.]
+            .iter()
+            .map(|u| {
+                let y = ss
+                    .in_step_out(vec![jar::OSSElEncoderAngle::with(vec![*u])])
+                    .unwrap()
+                    .unwrap();
+                y.iter()
+                    .flat_map(|y| Option::<Vec<f64>>::from(y).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn state_space_outputs() {
+        let mut m: BTreeMap<_, _> = matrices().into_iter().collect();
+        let mut take = |name| m.remove(name).unwrap();
+        let ss = StateSpace::new(take("A"), take("B"), take("C"), take("D")).unwrap();
+        let y = vec![vec![0., 1.], vec![1., 2.], vec![2.5, -0.5]];
+        assert_eq!(run(ss), y);
+
+        let path = std::env::temp_dir().join(format!("dos-state-space-{}", std::process::id()));
+        let npz = path.with_extension("npz");
+        let mut zip = zip::ZipWriter::new(File::create(&npz).unwrap());
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, rows) in matrices() {
+            let header = format!(
+                "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {}), }}",
+                rows.len(),
+                rows[0].len()
+            );
+            zip.start_file(format!("{}.npy", name), options).unwrap();
+            zip.write_all(b"\x93NUMPY\x01\x00").unwrap();
+            zip.write_all(&(header.len() as u16).to_le_bytes()).unwrap();
+            zip.write_all(header.as_bytes()).unwrap();
+            for x in rows.iter().flatten() {
+                zip.write_all(&x.to_le_bytes()).unwrap();
+            }
+        }
+        zip.finish().unwrap();
+        assert_eq!(run(StateSpace::from_file(&npz).unwrap()), y);
+        std::fs::remove_file(npz).unwrap();
+
+        let pickle = path.with_extension("pkl");
+        let m: BTreeMap<_, _> = matrices().into_iter().collect();
+        pkl::to_writer(&mut File::create(&pickle).unwrap(), &m, true).unwrap();
+        assert_eq!(run(StateSpace::from_file(&pickle).unwrap()), y);
+        std::fs::remove_file(pickle).unwrap();
+
+        assert!(StateSpace::from_file(path.with_extension("mat")).is_err());
+    }
+}
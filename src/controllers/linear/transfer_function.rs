@@ -0,0 +1,81 @@
+//! Discrete transfer function
+//!
+//! The transfer function is given by the coefficients of the numerator and denominator polynomials in $`z^{-1}`$:
+//! ```math
+//! H(z) = {b_0 + b_1 z^{-1} + \dots + b_n z^{-n} \over a_0 + a_1 z^{-1} + \dots + a_n z^{-n}}
+//! ```
+//! and it is implemented with the transposed direct form II.
+
+use super::{Filter, LinearError, Result};
+use crate::DOSError;
+
+/// Discrete transfer function
+#[derive(Debug, Clone, Default)]
+pub struct TransferFunction {
+    num: Vec<f64>,
+    den: Vec<f64>,
+    state: Vec<f64>,
+}
+impl TransferFunction {
+    /// Creates a transfer function from the numerator and denominator coefficients in increasing powers of $`z^{-1}`$
+    pub fn new(mut num: Vec<f64>, mut den: Vec<f64>) -> Result<Self> {
+        let a0 = den
+            .first()
+            .cloned()
+            .filter(|a0| *a0 != 0f64)
+            .ok_or(DOSError::Component(LinearError::Denominator))?;
+        if num.is_empty() {
+            num.push(0f64);
+        }
+        let n = num.len().max(den.len());
+        num.resize(n, 0f64);
+        den.resize(n, 0f64);
+        num.iter_mut().chain(den.iter_mut()).for_each(|x| *x /= a0);
+        Ok(Self {
+            num,
+            den,
+            state: vec![0f64; n - 1],
+        })
+    }
+    /// Creates a static gain
+    pub fn gain(k: f64) -> Self {
+        Self {
+            num: vec![k],
+            den: vec![1f64],
+            state: vec![],
+        }
+    }
+    /// Creates a lead-lag compensator
+    ///
+    /// The continuous compensator $`K(1+s/\omega_z)/(1+s/\omega_p)`$, with the zero and the pole frequencies in Hz, is discretized with the bilinear transform.
+    /// The compensator is a phase lead if the zero frequency is lower than the pole frequency and a phase lag otherwise.
+    pub fn lead_lag(sampling_rate: f64, gain: f64, zero: f64, pole: f64) -> Result<Self> {
+        let c = 2. * sampling_rate;
+        let (cz, cp) = (
+            c / (2. * std::f64::consts::PI * zero),
+            c / (2. * std::f64::consts::PI * pole),
+        );
+        Self::new(
+            vec![gain * (1. + cz), gain * (1. - cz)],
+            vec![1. + cp, 1. - cp],
+        )
+    }
+    /// Returns the normalized numerator and denominator coefficients
+    pub fn coefficients(&self) -> (&[f64], &[f64]) {
+        (&self.num, &self.den)
+    }
+}
+impl Filter for TransferFunction {
+    fn update(&mut self, u: f64) -> f64 {
+        let y = self.num[0] * u + self.state.first().cloned().unwrap_or_default();
+        let n = self.state.len();
+        for i in 0..n {
+            let next = if i + 1 < n { self.state[i + 1] } else { 0f64 };
+            self.state[i] = next + self.num[i + 1] * u - self.den[i + 1] * y;
+        }
+        y
+    }
+    fn reset(&mut self) {
+        self.state.iter_mut().for_each(|x| *x = 0f64);
+    }
+}
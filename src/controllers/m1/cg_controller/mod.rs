@@ -1,10 +1,11 @@
 use crate::{
-    build_controller, build_inputs, build_outputs, import_simulink,
+    build_controller, build_inputs, build_outputs,
+    controllers::SimulinkStates,
     io::{jar, Tags},
     IOTags, DOS, IO,
 };
 
-import_simulink!(M1OFL_Control, U : (HP_LC,42), Y : (M1_Rel_F,42));
+include!(concat!(env!("OUT_DIR"), "/src/controllers/m1/cg_controller/import_simulink.rs"));
 build_inputs!(M1HPLC, 42);
 build_outputs!(M1CGFM, 42);
 build_controller!(M1OFL_Control,
@@ -14,23 +15,26 @@ build_controller!(M1OFL_Control,
 
 impl<'a> IOTags for Controller<'a> {
     fn outputs_tags(&self) -> Vec<Tags> {
-        vec![jar::M1CGFM::new()]
+        let mut tags = vec![jar::M1CGFM::new()];
+        tags.extend(self.states_tags());
+        tags
     }
     fn inputs_tags(&self) -> Vec<Tags> {
         vec![jar::M1HPLC::new()]
     }
 }
 impl<'a> DOS for Controller<'a> {
+    fn reset(&mut self) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        Simulink::reset(self);
+        Ok(self)
+    }
     fn inputs(&mut self, data: Vec<IO<Vec<f64>>>) -> Result<&mut Self, Box<dyn std::error::Error>> {
         if data.into_iter().fold(1, |mut a, io| {
-            match io {
-                IO::M1HPLC { data: Some(values) } => {
-                    for (k, v) in values.into_iter().enumerate() {
-                        self.m1_hp_lc[k] = v;
-                    }
-                    a -= 1;
+            if let IO::M1HPLC { data: Some(values) } = io {
+                for (k, v) in values.into_iter().enumerate() {
+                    self.m1_hp_lc[k] = v;
                 }
-                _ => (),
+                a -= 1;
             }
             if a == 0 {
                 return a;
@@ -44,8 +48,10 @@ impl<'a> DOS for Controller<'a> {
         }
     }
     fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
-        Some(vec![IO::M1CGFM {
+        let mut y = vec![IO::M1CGFM {
             data: Some(Vec::<f64>::from(&self.m1_cg_fm)),
-        }])
+        }];
+        y.extend(self.states_outputs());
+        Some(y)
     }
 }
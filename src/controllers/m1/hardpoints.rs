@@ -0,0 +1,343 @@
+//! M1 hardpoints commands
+//!
+//! The [`HardpointsCommands`] source outputs the [`M1HPCmd`](crate::io::IO::M1HPCmd) commands of the 42 M1 hardpoints (6 per segment) to the hardpoints load cells model.
+//! The commands are given by a [`Schedule`], either built in Rust or read from a pickle file, e.g. the active optics corrections applied every 30s.
+//! The commands are held from one schedule time to the next or, optionally, linearly ramped from the current commands.
+//! In the binaries, the schedule is read from the pickle file given by the `M1_HP_SCHEDULE` environment variable, the commands are 0 if it is not set.
+//!
+//! The commands corresponding to the rigid body motions of the segments are computed with [`RigidBodyToHardpoints`].
+//!
+//! # Example
+//! A 1 micron piston of segment #1 after 30s
+//! ```no_run
+//! use dos::{
+//!     controllers::m1::hardpoints::{HardpointsCommands, RigidBodyToHardpoints},
+//!     DOS,
+//! };
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let rbm2hp = RigidBodyToHardpoints::from_pickle("m1_rbm2hp.pkl")?;
+//!     let mut rbm = vec![0f64; 42];
+//!     rbm[2] = 1e-6;
+//!     let mut m1_hp_cmd = HardpointsCommands::new(100.0)
+//!         .command(30.0, rbm2hp.hardpoints(&rbm)?)?
+//!         .ramp(1.0)
+//!         .build();
+//!     while let Some(m1_hp) = m1_hp_cmd.outputs() {
+//!         // ...
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    io::{jar, Tags},
+    DOSError, IOTags, DOS, IO,
+};
+use serde::{Deserialize, Serialize};
+use serde_pickle as pkl;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Number of M1 hardpoints
+pub const N_HARDPOINT: usize = 42;
+
+#[derive(Debug)]
+pub enum HardpointsError {
+    /// Wrong number of values
+    Size(usize),
+    /// Schedule time vector and commands of different lengths
+    Schedule,
+}
+type Result<T> = std::result::Result<T, DOSError<HardpointsError>>;
+
+/// Hardpoints commands schedule
+///
+/// In Python, a schedule is the dictionary `{'time': [0, 30, 60], 'commands': [[...], [...], [...]]}`
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Schedule {
+    /// Times in s at which the commands are applied
+    pub time: Vec<f64>,
+    /// Hardpoints commands, 42 per time
+    pub commands: Vec<Vec<f64>>,
+}
+impl Schedule {
+    /// Reads a schedule from a pickle file
+    pub fn from_pickle<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = File::open(path)?;
+        let schedule: Schedule = pkl::from_reader(BufReader::new(f))?;
+        if schedule.time.len() != schedule.commands.len() {
+            return Err(DOSError::Component(HardpointsError::Schedule));
+        }
+        if let Some(cmd) = schedule.commands.iter().find(|x| x.len() != N_HARDPOINT) {
+            return Err(DOSError::Component(HardpointsError::Size(cmd.len())));
+        }
+        Ok(schedule)
+    }
+}
+
+/// Linear transformation from the M1 segments rigid body motions to the hardpoints commands
+///
+/// The rigid body motions of a segment are $`[T_x,T_y,T_z,R_x,R_y,R_z]`$ in the segment local coordinate system
+pub struct RigidBodyToHardpoints {
+    /// 7 6x6 matrices in row major order
+    pub matrices: Vec<Vec<f64>>,
+}
+impl RigidBodyToHardpoints {
+    /// Creates the transformation from the 7 6x6 matrices in row major order
+    pub fn new(matrices: Vec<Vec<f64>>) -> Result<Self> {
+        if matrices.len() != 7 {
+            return Err(DOSError::Component(HardpointsError::Size(matrices.len())));
+        }
+        if let Some(m) = matrices.iter().find(|m| m.len() != 36) {
+            return Err(DOSError::Component(HardpointsError::Size(m.len())));
+        }
+        Ok(Self { matrices })
+    }
+    /// Reads the 7 6x6 matrices in row major order from a pickle file
+    pub fn from_pickle<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = File::open(path)?;
+        Self::new(pkl::from_reader(BufReader::new(f))?)
+    }
+    /// Creates the transformation from the hardpoints geometry
+    ///
+    /// For each segment, the geometry is the location of the 6 hardpoints attachment points and the unit vectors of the hardpoints axes in the segment local coordinate system.
+    /// The hardpoint command is the displacement of the attachment point along the hardpoint axis: $`d=n\cdot(T+R\times r)`$
+    pub fn from_geometry(geometry: &[[([f64; 3], [f64; 3]); 6]]) -> Result<Self> {
+        Self::new(
+            geometry
+                .iter()
+                .map(|segment| {
+                    segment
+                        .iter()
+                        .flat_map(|(r, n)| {
+                            vec![
+                                n[0],
+                                n[1],
+                                n[2],
+                                r[1] * n[2] - r[2] * n[1],
+                                r[2] * n[0] - r[0] * n[2],
+                                r[0] * n[1] - r[1] * n[0],
+                            ]
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+    /// Returns the 42 hardpoints commands corresponding to the 42 rigid body motions of the segments
+    pub fn hardpoints(&self, rbm: &[f64]) -> Result<Vec<f64>> {
+        if rbm.len() != N_HARDPOINT {
+            return Err(DOSError::Component(HardpointsError::Size(rbm.len())));
+        }
+        Ok(self
+            .matrices
+            .iter()
+            .zip(rbm.chunks(6))
+            .flat_map(|(m, rbm)| {
+                m.chunks(6)
+                    .map(|row| row.iter().zip(rbm).map(|(m, x)| m * x).sum::<f64>())
+                    .collect::<Vec<f64>>()
+            })
+            .collect())
+    }
+}
+
+/// Hardpoints commands builder
+pub struct HardpointsCommands {
+    sampling_rate: f64,
+    n_sample: Option<usize>,
+    schedule: Schedule,
+    ramp: f64,
+}
+impl HardpointsCommands {
+    /// Creates a new hardpoints commands builder with the `sampling_rate` in Hz
+    ///
+    /// The commands are 0 until the first schedule time
+    pub fn new(sampling_rate: f64) -> Self {
+        Self {
+            sampling_rate,
+            n_sample: None,
+            schedule: Default::default(),
+            ramp: 0f64,
+        }
+    }
+    /// Sets the number of samples, the commands never end if it is not set
+    pub fn n_sample(self, n_sample: usize) -> Self {
+        Self {
+            n_sample: Some(n_sample),
+            ..self
+        }
+    }
+    /// Applies the commands `cmd` at time `t`
+    pub fn command(mut self, t: f64, cmd: Vec<f64>) -> Result<Self> {
+        if cmd.len() != N_HARDPOINT {
+            return Err(DOSError::Component(HardpointsError::Size(cmd.len())));
+        }
+        let k = self
+            .schedule
+            .time
+            .iter()
+            .position(|x| *x > t)
+            .unwrap_or(self.schedule.time.len());
+        self.schedule.time.insert(k, t);
+        self.schedule.commands.insert(k, cmd);
+        Ok(self)
+    }
+    /// Applies the commands `commands` one after the other every `period` seconds starting at `t_start`
+    pub fn every(self, t_start: f64, period: f64, commands: Vec<Vec<f64>>) -> Result<Self> {
+        commands
+            .into_iter()
+            .enumerate()
+            .try_fold(self, |this, (k, cmd)| {
+                this.command(t_start + k as f64 * period, cmd)
+            })
+    }
+    /// Applies the commands of a schedule
+    pub fn schedule(self, schedule: Schedule) -> Result<Self> {
+        if schedule.time.len() != schedule.commands.len() {
+            return Err(DOSError::Component(HardpointsError::Schedule));
+        }
+        schedule
+            .time
+            .into_iter()
+            .zip(schedule.commands)
+            .try_fold(self, |this, (t, cmd)| this.command(t, cmd))
+    }
+    /// Linearly ramps the commands from one value to the next over `duration` seconds
+    ///
+    /// If a new command is applied before the end of the previous ramp, the new ramp starts from the current commands
+    pub fn ramp(self, duration: f64) -> Self {
+        Self {
+            ramp: duration,
+            ..self
+        }
+    }
+    /// Builds a hardpoints commands source object
+    pub fn build(self) -> HardpointsCommandsSource {
+        let Schedule { time, commands } = &self.schedule;
+        let mut starts = vec![vec![0f64; N_HARDPOINT]];
+        for k in 1..time.len() {
+            let start = ramp(
+                &starts[k - 1],
+                &commands[k - 1],
+                time[k] - time[k - 1],
+                self.ramp,
+            );
+            starts.push(start);
+        }
+        HardpointsCommandsSource {
+            sampling_rate: self.sampling_rate,
+            n_sample: self.n_sample,
+            step: 0,
+            schedule: self.schedule,
+            ramp: self.ramp,
+            starts,
+        }
+    }
+}
+
+// The commands `dt` seconds into the ramp of `duration` seconds from `start` to `target`
+fn ramp(start: &[f64], target: &[f64], dt: f64, duration: f64) -> Vec<f64> {
+    if dt >= duration {
+        return target.to_vec();
+    }
+    let w = dt / duration;
+    start
+        .iter()
+        .zip(target.iter())
+        .map(|(s, t)| s + w * (t - s))
+        .collect()
+}
+
+/// Hardpoints commands source
+pub struct HardpointsCommandsSource {
+    pub sampling_rate: f64,
+    pub n_sample: Option<usize>,
+    step: usize,
+    schedule: Schedule,
+    ramp: f64,
+    // Commands at the start of each ramp
+    starts: Vec<Vec<f64>>,
+}
+impl HardpointsCommandsSource {
+    /// Returns the hardpoints commands at time `t`
+    pub fn commands(&self, t: f64) -> Vec<f64> {
+        let Schedule { time, commands } = &self.schedule;
+        match time.iter().rposition(|x| *x <= t) {
+            None => vec![0f64; N_HARDPOINT],
+            Some(k) => ramp(&self.starts[k], &commands[k], t - time[k], self.ramp),
+        }
+    }
+}
+impl IOTags for HardpointsCommandsSource {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        vec![jar::M1HPCmd::new()]
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        vec![]
+    }
+}
+impl DOS for HardpointsCommandsSource {
+    fn inputs(
+        &mut self,
+        _: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        Err("HardpointsCommandsSource takes no inputs".into())
+    }
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        self.step = 0;
+        Ok(self)
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        if matches!(self.n_sample, Some(n) if self.step >= n) {
+            return None;
+        }
+        let t = self.step as f64 / self.sampling_rate;
+        self.step += 1;
+        Some(vec![jar::M1HPCmd::with(self.commands(t))])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hardpoints_schedule() {
+        let mut source = HardpointsCommands::new(1.)
+            .every(2., 2., vec![vec![1f64; 42], vec![3f64; 42]])
+            .unwrap()
+            .ramp(1.)
+            .n_sample(6)
+            .build();
+        let mut y = vec![];
+        while let Some(cmd) = source.outputs() {
+            y.push(Option::<Vec<f64>>::from(&cmd[0]).unwrap()[0]);
+        }
+        assert_eq!(y, vec![0., 0., 0., 1., 1., 3.]);
+        assert_eq!(source.commands(4.5)[41], 2.);
+        let source = HardpointsCommands::new(1.)
+            .command(0., vec![4f64; 42])
+            .and_then(|x| x.command(1., vec![0f64; 42]))
+            .unwrap()
+            .ramp(2.)
+            .build();
+        assert_eq!(source.commands(1.)[0], 2.);
+        assert_eq!(source.commands(2.)[0], 1.);
+        assert_eq!(source.commands(3.)[0], 0.);
+        let geometry = [[([1., 0., 0.], [0., 0., 1.]); 6]; 7];
+        let rbm2hp = RigidBodyToHardpoints::from_geometry(&geometry).unwrap();
+        let mut rbm = vec![0f64; 42];
+        rbm[2] = 1.;
+        rbm[10] = 1.;
+        let hp = rbm2hp.hardpoints(&rbm).unwrap();
+        assert!(hp[..6].iter().all(|x| *x == 1.));
+        assert!(hp[6..12].iter().all(|x| *x == -1.));
+        assert!(rbm2hp.hardpoints(&rbm[..41]).is_err());
+        assert!(HardpointsCommands::new(1.)
+            .command(0., vec![0f64; 6])
+            .is_err());
+    }
+}
@@ -1,10 +1,11 @@
 use crate::{
-    build_controller, build_inputs, build_outputs, import_simulink,
+    build_controller, build_inputs, build_outputs,
+    controllers::SimulinkStates,
     io::{jar, Tags},
     IOTags, DOS, IO,
 };
 
-import_simulink!(M1HPloadcells, U : (M1_HP_D,84,M1_HP_cmd,42), Y : (M1_HP_LC,42));
+include!(concat!(env!("OUT_DIR"), "/src/controllers/m1/hp_load_cells/import_simulink.rs"));
 build_inputs!(M1HpD, 84, M1HpCmd, 42);
 build_outputs!(M1HpLc, 42);
 build_controller!(M1HPloadcells,
@@ -15,13 +16,19 @@ build_controller!(M1HPloadcells,
 
 impl<'a> IOTags for Controller<'a> {
     fn outputs_tags(&self) -> Vec<Tags> {
-        vec![jar::M1HPLC::new()]
+        let mut tags = vec![jar::M1HPLC::new()];
+        tags.extend(self.states_tags());
+        tags
     }
     fn inputs_tags(&self) -> Vec<Tags> {
         vec![jar::OSSHardpointD::new(), jar::M1HPCmd::new()]
     }
 }
 impl<'a> DOS for Controller<'a> {
+    fn reset(&mut self) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        Simulink::reset(self);
+        Ok(self)
+    }
     fn inputs(&mut self, data: Vec<IO<Vec<f64>>>) -> Result<&mut Self, Box<dyn std::error::Error>> {
         if data.into_iter().fold(2, |mut a, io| {
             match io {
@@ -51,8 +58,10 @@ impl<'a> DOS for Controller<'a> {
         }
     }
     fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
-        Some(vec![IO::M1HPLC {
+        let mut y = vec![IO::M1HPLC {
             data: Some(Vec::<f64>::from(&self.m1_hp_lc)),
-        }])
+        }];
+        y.extend(self.states_outputs());
+        Some(y)
     }
 }
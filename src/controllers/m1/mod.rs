@@ -1,3 +1,7 @@
+pub mod hardpoints;
+#[cfg(feature = "m1")]
 pub mod hp_load_cells;
+#[cfg(feature = "m1")]
 pub mod local_controller;
+#[cfg(feature = "m1")]
 pub mod cg_controller;
@@ -0,0 +1,372 @@
+//! ASM inner loop
+//!
+//! The adaptive secondary mirror (ASM) face sheet of each M2 segment is positioned by voice-coil actuators with respect to the reference body.
+//! The [`Asm`] model is the local position loop of the voice coils of the 7 segments:
+//!  - the face sheet axial displacements [`M2ReferenceBody{1..7}AxialD`](crate::io::IO::M2ReferenceBody1AxialD) of a segment are projected into either the actuators space (zonal) or the modes space (modal) with the segment sensors matrix,
+//!  - a copy of the same [`Filter`] turns the difference between the command and the projected displacement into a voice coil force for each actuator or mode,
+//!  - the voice coil forces are summed into the forces and moments applied to the segment reference body [`MCM2RB6F`](crate::io::IO::MCM2RB6F) with the segment actuators matrix,
+//!    the reaction is applied to the segment cold plate [`MCM2CP6F`](crate::io::IO::MCM2CP6F).
+//!
+//! The sensors and actuators matrices are either given with [`AsmSegment::new`], computed from the nodes and actuators locations with [`AsmSegment::zonal`] and [`AsmSegment::modal`] or read from a pickle file with [`Asm::from_pickle`].
+//! The ASM commands [`M2ASMCmd`](crate::io::IO::M2ASMCmd) are an optional input, they are set to 0 by default.
+//!
+//! # Example
+//! M2 wind loads rejection by the ASM modal piston, tip and tilt loop
+//! ```no_run
+//! use dos::{
+//!     controllers::{
+//!         linear::Pid,
+//!         m2::{asm::AsmSegment, Asm},
+//!         state_space::DiscreteStateSpace,
+//!     },
+//!     io::jar,
+//!     WindLoads, DOS,
+//! };
+//! use fem::FEM;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let sampling_rate = 1e3;
+//!     let mut wind_loading = WindLoads::from_pickle("wind_loads.pkl")?
+//!         .m2_asm_reference_bodies()?
+//!         .build()?;
+//!     // the face sheet nodes (x,y) coordinates of the 7 segments
+//!     let nodes: Vec<Vec<[f64; 2]>> = vec![];
+//!     let segments = nodes
+//!         .iter()
+//!         .map(|nodes| AsmSegment::modal(nodes))
+//!         .collect::<Result<Vec<_>, _>>()?;
+//!     let mut asm = Asm::new(
+//!         Pid::new(sampling_rate, 1e5, 1e7, 1e2).derivative_filter(200.),
+//!         segments,
+//!     )?;
+//!     let mut fem = DiscreteStateSpace::from(FEM::from_pickle("modal_state_space_model_2ndOrder.pkl")?)
+//!         .sampling(sampling_rate)
+//!         .inputs_from(&wind_loading)
+//!         .inputs(vec![jar::MCM2CP6F::new()])
+//!         .outputs_to(&asm)
+//!         .build()?;
+//!     let mut asm_forces = asm.outputs().ok_or("ASM output is empty")?;
+//!     while let Some(mut fem_forces) = wind_loading.outputs() {
+//!         fem_forces[jar::MCM2RB6F::new()] += &asm_forces[0];
+//!         fem_forces.push(asm_forces[1].clone());
+//!         let face_sheets = fem.in_step_out(fem_forces)?.ok_or("FEM output is empty")?;
+//!         asm_forces = asm.in_step_out(face_sheets)?.ok_or("ASM output is empty")?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    controllers::linear::Filter,
+    io::{jar, IOError, Tags},
+    DOSError, IOTags, DOS, IO,
+};
+use nalgebra::{DMatrix, DVector};
+use serde::Deserialize;
+use serde_pickle as pkl;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Number of M2 segments
+pub const N_SEGMENT: usize = 7;
+
+#[derive(Debug)]
+pub enum AsmError {
+    /// Matrices or inputs of inconsistent sizes
+    Size(String),
+    /// Singular modal projection
+    Modes(String),
+    /// Input missing from the inputs data
+    Input(Tags),
+}
+type Result<T> = std::result::Result<T, DOSError<AsmError>>;
+
+/// ASM segment sensors and actuators matrices
+///
+/// In Python, a segment is the dictionary `{'sensors': [[...]], 'actuators': [[...]]}`
+#[derive(Deserialize, Debug, Clone)]
+pub struct AsmSegment {
+    /// Projection of the face sheet axial displacements into the actuators or modes space, `n x n_node` list of rows
+    pub sensors: Vec<Vec<f64>>,
+    /// Sum of the actuators or modal forces into the reference body forces and moments, `6 x n` list of rows
+    pub actuators: Vec<Vec<f64>>,
+}
+impl AsmSegment {
+    /// Creates a segment from the sensors and actuators matrices
+    pub fn new(sensors: Vec<Vec<f64>>, actuators: Vec<Vec<f64>>) -> Result<Self> {
+        let n = sensors.len();
+        let n_node = sensors.first().map_or(0, |x| x.len());
+        if sensors.iter().any(|x| x.len() != n_node) {
+            return Err(DOSError::Component(AsmError::Size(
+                "sensors rows of different lengths".to_owned(),
+            )));
+        }
+        if actuators.len() != 6 || actuators.iter().any(|x| x.len() != n) {
+            return Err(DOSError::Component(AsmError::Size(format!(
+                "actuators is not 6x{}",
+                n
+            ))));
+        }
+        Ok(Self { sensors, actuators })
+    }
+    /// Creates a zonal segment from the face sheet nodes and the actuators (x,y) locations in the segment local coordinate system
+    ///
+    /// The displacement at an actuator is the displacement of the nearest node
+    /// and an actuator axial force $`f`$ at $`(x,y)`$ adds $`[0,0,f,yf,-xf,0]`$ to the reference body forces and moments
+    pub fn zonal(nodes: &[[f64; 2]], actuators: &[[f64; 2]]) -> Result<Self> {
+        let sensors = actuators
+            .iter()
+            .map(|a| {
+                let nearest = nodes
+                    .iter()
+                    .map(|n| (n[0] - a[0]).hypot(n[1] - a[1]))
+                    .enumerate()
+                    .fold(
+                        (0, f64::INFINITY),
+                        |m, (k, d)| if d < m.1 { (k, d) } else { m },
+                    )
+                    .0;
+                let mut row = vec![0f64; nodes.len()];
+                if let Some(x) = row.get_mut(nearest) {
+                    *x = 1f64;
+                }
+                row
+            })
+            .collect();
+        let mut forces = vec![vec![0f64; actuators.len()]; 6];
+        for (k, a) in actuators.iter().enumerate() {
+            forces[2][k] = 1f64;
+            forces[3][k] = a[1];
+            forces[4][k] = -a[0];
+        }
+        Self::new(sensors, forces)
+    }
+    /// Creates a modal segment for the piston, tip and tilt of the face sheet from the face sheet nodes (x,y) locations in the segment local coordinate system
+    ///
+    /// The modes $`[T_z,R_x,R_y]`$ are the least square fit of the axial displacements $`d=T_z+R_xy-R_yx`$
+    /// and the modal forces are the reference body axial force and moments $`[F_z,M_x,M_y]`$
+    pub fn modal(nodes: &[[f64; 2]]) -> Result<Self> {
+        let h = DMatrix::from_fn(nodes.len(), 3, |i, j| match j {
+            0 => 1f64,
+            1 => nodes[i][1],
+            _ => -nodes[i][0],
+        });
+        let p = h
+            .pseudo_inverse(1e-12)
+            .map_err(|e| DOSError::Component(AsmError::Modes(e.to_owned())))?;
+        if p.rank(1e-12) < 3 {
+            return Err(DOSError::Component(AsmError::Modes(format!(
+                "{} nodes are not enough for piston, tip and tilt",
+                nodes.len()
+            ))));
+        }
+        let sensors = p.row_iter().map(|r| r.iter().cloned().collect()).collect();
+        let mut forces = vec![vec![0f64; 3]; 6];
+        for k in 0..3 {
+            forces[k + 2][k] = 1f64;
+        }
+        Self::new(sensors, forces)
+    }
+}
+
+/// ASM inner loop of the 7 M2 segments
+pub struct Asm<F> {
+    sensors: Vec<DMatrix<f64>>,
+    actuators: Vec<DMatrix<f64>>,
+    filters: Vec<Vec<F>>,
+    cmd: Vec<Vec<f64>>,
+    nodes: Vec<DVector<f64>>,
+    forces: Vec<Vec<f64>>,
+}
+impl<F: Filter + Clone> Asm<F> {
+    /// Creates the ASM inner loop of the 7 segments, the same `filter` is applied to each actuator or mode
+    pub fn new(filter: F, segments: Vec<AsmSegment>) -> Result<Self> {
+        if segments.len() != N_SEGMENT {
+            return Err(DOSError::Component(AsmError::Size(format!(
+                "{} segments instead of {}",
+                segments.len(),
+                N_SEGMENT
+            ))));
+        }
+        let matrix = |m: Vec<Vec<f64>>| {
+            let n_col = m.first().map_or(0, |x| x.len());
+            DMatrix::from_row_slice(
+                m.len(),
+                n_col,
+                &m.into_iter().flatten().collect::<Vec<f64>>(),
+            )
+        };
+        let (sensors, actuators): (Vec<_>, Vec<_>) = segments
+            .into_iter()
+            .map(|s| AsmSegment::new(s.sensors, s.actuators))
+            .map(|s| s.map(|s| (matrix(s.sensors), matrix(s.actuators))))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        Ok(Self {
+            filters: sensors
+                .iter()
+                .map(|s| vec![filter.clone(); s.nrows()])
+                .collect(),
+            cmd: sensors.iter().map(|s| vec![0f64; s.nrows()]).collect(),
+            nodes: sensors.iter().map(|s| DVector::zeros(s.ncols())).collect(),
+            forces: sensors.iter().map(|s| vec![0f64; s.nrows()]).collect(),
+            sensors,
+            actuators,
+        })
+    }
+    /// Reads the 7 segments sensors and actuators matrices from a pickle file
+    ///
+    /// In Python, the file is the list of the 7 [`AsmSegment`] dictionaries
+    pub fn from_pickle<P: AsRef<Path>>(filter: F, path: P) -> Result<Self> {
+        let f = File::open(path)?;
+        Self::new(filter, pkl::from_reader(BufReader::new(f))?)
+    }
+    /// Returns the voice coils forces of each segment, either the actuators or the modal forces
+    pub fn voice_coils_forces(&self) -> &[Vec<f64>] {
+        &self.forces
+    }
+    /// Returns the filters of each segment, one per actuator or mode
+    pub fn filters(&self) -> &[Vec<F>] {
+        &self.filters
+    }
+}
+impl<F: Filter + Clone> Iterator for Asm<F> {
+    type Item = ();
+    fn next(&mut self) -> Option<Self::Item> {
+        for (((filters, forces), cmd), (sensors, nodes)) in self
+            .filters
+            .iter_mut()
+            .zip(self.forces.iter_mut())
+            .zip(self.cmd.iter())
+            .zip(self.sensors.iter().zip(self.nodes.iter()))
+        {
+            let y = sensors * nodes;
+            *forces = filters
+                .iter_mut()
+                .zip(cmd.iter().zip(y.iter()))
+                .map(|(f, (c, y))| f.update(c - y))
+                .collect();
+        }
+        Some(())
+    }
+}
+/// The ASM commands [`M2ASMCmd`](crate::io::IO::M2ASMCmd) are an optional input, they are not listed in the inputs tags
+impl<F> IOTags for Asm<F> {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        vec![jar::MCM2RB6F::new(), jar::MCM2CP6F::new()]
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        vec![
+            jar::M2ReferenceBody1AxialD::new(),
+            jar::M2ReferenceBody2AxialD::new(),
+            jar::M2ReferenceBody3AxialD::new(),
+            jar::M2ReferenceBody4AxialD::new(),
+            jar::M2ReferenceBody5AxialD::new(),
+            jar::M2ReferenceBody6AxialD::new(),
+            jar::M2ReferenceBody7AxialD::new(),
+        ]
+    }
+}
+impl<F: Filter + Clone> DOS for Asm<F> {
+    fn inputs(
+        &mut self,
+        data: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        let tags = self.inputs_tags();
+        let mut found = [false; N_SEGMENT];
+        for io in data {
+            if let IO::M2ASMCmd { .. } = io {
+                let cmd = std::result::Result::<Vec<f64>, DOSError<IOError>>::from(io)?;
+                let n: usize = self.cmd.iter().map(|x| x.len()).sum();
+                if cmd.len() != n {
+                    return Err(DOSError::Component(AsmError::Size(format!(
+                        "{} commands instead of {}",
+                        cmd.len(),
+                        n
+                    )))
+                    .into());
+                }
+                let mut values = cmd.into_iter();
+                for c in self.cmd.iter_mut() {
+                    c.iter_mut().zip(&mut values).for_each(|(c, v)| *c = v);
+                }
+            } else if let Some(sid) = tags.iter().position(|t| io == *t) {
+                let d = std::result::Result::<Vec<f64>, DOSError<IOError>>::from(io)?;
+                if d.len() != self.nodes[sid].len() {
+                    return Err(DOSError::Component(AsmError::Size(format!(
+                        "{} face sheet nodes instead of {} on segment #{}",
+                        d.len(),
+                        self.nodes[sid].len(),
+                        sid + 1
+                    )))
+                    .into());
+                }
+                self.nodes[sid] = DVector::from_vec(d);
+                found[sid] = true;
+            }
+        }
+        match found.iter().position(|x| !x) {
+            Some(sid) => Err(DOSError::Component(AsmError::Input(tags[sid].clone())).into()),
+            None => Ok(self),
+        }
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        let rb: Vec<f64> = self
+            .actuators
+            .iter()
+            .zip(self.forces.iter())
+            .flat_map(|(a, f)| (a * DVector::from_column_slice(f)).as_slice().to_vec())
+            .collect();
+        let cp = rb.iter().map(|x| -x).collect();
+        Some(vec![jar::MCM2RB6F::with(rb), jar::MCM2CP6F::with(cp)])
+    }
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        self.filters.iter_mut().flatten().for_each(|f| f.reset());
+        self.cmd.iter_mut().flatten().for_each(|x| *x = 0f64);
+        self.nodes.iter_mut().for_each(|x| x.fill(0f64));
+        self.forces.iter_mut().flatten().for_each(|x| *x = 0f64);
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controllers::linear::Pid;
+
+    #[test]
+    fn asm_modal_and_zonal() {
+        let nodes: Vec<[f64; 2]> = (0..25)
+            .map(|k| [(k % 5) as f64 * 0.1 - 0.2, (k / 5) as f64 * 0.1 - 0.2])
+            .collect();
+        // piston of 1 and tilt of 2 around y
+        let d: Vec<f64> = nodes.iter().map(|n| 1. - 2. * n[0]).collect();
+        let modal = AsmSegment::modal(&nodes).unwrap();
+        let mut asm = Asm::new(Pid::new(1e3, 1., 0., 0.), vec![modal; 7]).unwrap();
+        let u: Vec<IO<Vec<f64>>> = asm
+            .inputs_tags()
+            .iter()
+            .map(|t| IO::<Vec<f64>>::from((t, d.clone())))
+            .collect();
+        assert!(asm.inputs(u[1..].to_vec()).is_err());
+        let y = asm.in_step_out(u.clone()).unwrap().unwrap();
+        let rb = Option::<Vec<f64>>::from(&y[0]).unwrap();
+        let cp = Option::<Vec<f64>>::from(&y[1]).unwrap();
+        assert_eq!(rb.len(), 42);
+        assert!(rb[..6]
+            .iter()
+            .zip(&[0., 0., -1., 0., -2., 0.])
+            .all(|(x, y)| (x - y).abs() < 1e-9));
+        assert!(rb.iter().zip(cp.iter()).all(|(x, y)| x + y == 0.));
+        // an actuator at each node
+        let zonal = AsmSegment::zonal(&nodes, &nodes).unwrap();
+        let mut asm = Asm::new(Pid::new(1e3, 1., 0., 0.), vec![zonal; 7]).unwrap();
+        asm.in_step_out(u).unwrap();
+        assert!(asm.voice_coils_forces()[6]
+            .iter()
+            .zip(d.iter())
+            .all(|(f, d)| f + d == 0.));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod asm;
+#[doc(inline)]
+pub use asm::Asm;
+pub mod positioners;
+#[doc(inline)]
+pub use positioners::Positioners;
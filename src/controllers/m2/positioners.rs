@@ -0,0 +1,328 @@
+//! M2 positioners control
+//!
+//! Each M2 segment is attached to the top-end by an hexapod of 6 positioners.
+//! The FEM outputs [`MCM2SmHexD`](crate::io::IO::MCM2SmHexD) are the axial displacements of both ends of the 42 positioners
+//! and the FEM inputs [`MCM2SmHexF`](crate::io::IO::MCM2SmHexF) are the axial forces applied to both ends of the 42 positioners.
+//! Both are ordered per segment like the M1 hardpoints [`OSSHardpointD`](crate::io::IO::OSSHardpointD):
+//! the 6 values of the first ends of the segment positioners followed by the 6 values of the second ends.
+//!
+//! The [`Positioners`] controller computes the elongation of each positioner, $`e=d_2-d_1`$ where $`d_1`$ and $`d_2`$ are the displacements of the 2 ends of the positioner,
+//! and a copy of the same [`Filter`] turns the difference between the positioner command and the elongation into the positioner force $`f`$,
+//! $`f`$ is applied to the FEM as $`[-f,f]`$.
+//! The positioners commands [`M2PosCmd`](crate::io::IO::M2PosCmd) are an optional input, they are set to 0 by default
+//! so the controller holds the M2 segments in place against the wind loads.
+//! Alternatively, the M2 segments rigid body motions commands [`M2RBMCmd`](crate::io::IO::M2RBMCmd) are converted into positioners commands
+//! with the [`RigidBodyToPositioners`] transformation given to [`Positioners::rigid_body`].
+//!
+//! # Example
+//! M2 positioners PI controller
+//! ```no_run
+//! use dos::{
+//!     controllers::{linear::Pid, m2::Positioners, state_space::DiscreteStateSpace},
+//!     DOS,
+//! };
+//! use fem::FEM;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let sampling_rate = 1e3;
+//!     let mut m2_positioners = Positioners::new(Pid::new(sampling_rate, 1e7, 1e9, 0.));
+//!     let mut fem = DiscreteStateSpace::from(FEM::from_pickle("modal_state_space_model_2ndOrder.pkl")?)
+//!         .sampling(sampling_rate)
+//!         .inputs_from(&m2_positioners)
+//!         .outputs_to(&m2_positioners)
+//!         .build()?;
+//!     let mut m2_forces = m2_positioners.outputs();
+//!     while let Some(fem_forces) = m2_forces {
+//!         let m2_hex_d = fem.in_step_out(fem_forces)?.ok_or("FEM output is empty")?;
+//!         m2_forces = m2_positioners.in_step_out(m2_hex_d)?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    controllers::linear::Filter,
+    io::{jar, IOError, Tags},
+    DOSError, IOTags, DOS, IO,
+};
+use serde_pickle as pkl;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Number of M2 positioners
+pub const N_POSITIONER: usize = 42;
+
+#[derive(Debug)]
+pub enum PositionersError {
+    /// Wrong number of values
+    Size(usize),
+    /// Input missing from the inputs data
+    Input(Tags),
+    /// Rigid body motions commands without a rigid body to positioners transformation
+    RigidBody,
+}
+type Result<T> = std::result::Result<T, DOSError<PositionersError>>;
+
+/// Linear transformation from the M2 segments rigid body motions to the positioners commands
+///
+/// The rigid body motions of a segment are $`[T_x,T_y,T_z,R_x,R_y,R_z]`$ in the segment local coordinate system
+pub struct RigidBodyToPositioners {
+    /// 7 6x6 matrices in row major order
+    pub matrices: Vec<Vec<f64>>,
+}
+impl RigidBodyToPositioners {
+    /// Creates the transformation from the 7 6x6 matrices in row major order
+    pub fn new(matrices: Vec<Vec<f64>>) -> Result<Self> {
+        if matrices.len() != 7 {
+            return Err(DOSError::Component(PositionersError::Size(matrices.len())));
+        }
+        if let Some(m) = matrices.iter().find(|m| m.len() != 36) {
+            return Err(DOSError::Component(PositionersError::Size(m.len())));
+        }
+        Ok(Self { matrices })
+    }
+    /// Reads the 7 6x6 matrices in row major order from a pickle file
+    pub fn from_pickle<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = File::open(path)?;
+        Self::new(pkl::from_reader(BufReader::new(f))?)
+    }
+    /// Creates the transformation from the hexapods geometry
+    ///
+    /// For each segment, the geometry is the location of the 6 positioners attachment points on the segment and the unit vectors of the positioners axes in the segment local coordinate system.
+    /// The positioner command is the displacement of the attachment point along the positioner axis: $`e=n\cdot(T+R\times r)`$
+    pub fn from_geometry(geometry: &[[([f64; 3], [f64; 3]); 6]]) -> Result<Self> {
+        Self::new(
+            geometry
+                .iter()
+                .map(|segment| {
+                    segment
+                        .iter()
+                        .flat_map(|(r, n)| {
+                            vec![
+                                n[0],
+                                n[1],
+                                n[2],
+                                r[1] * n[2] - r[2] * n[1],
+                                r[2] * n[0] - r[0] * n[2],
+                                r[0] * n[1] - r[1] * n[0],
+                            ]
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+    /// Returns the 42 positioners commands corresponding to the 42 rigid body motions of the segments
+    pub fn positioners(&self, rbm: &[f64]) -> Result<Vec<f64>> {
+        if rbm.len() != N_POSITIONER {
+            return Err(DOSError::Component(PositionersError::Size(rbm.len())));
+        }
+        Ok(self
+            .matrices
+            .iter()
+            .zip(rbm.chunks(6))
+            .flat_map(|(m, rbm)| {
+                m.chunks(6)
+                    .map(|row| row.iter().zip(rbm).map(|(m, x)| m * x).sum::<f64>())
+                    .collect::<Vec<f64>>()
+            })
+            .collect())
+    }
+}
+
+// The elongations of the positioners from the displacements of both ends, see M1HPloadcells.c
+fn elongations(nodes: &[f64]) -> Vec<f64> {
+    nodes
+        .chunks(12)
+        .flat_map(|d| {
+            d[6..]
+                .iter()
+                .zip(&d[..6])
+                .map(|(d2, d1)| d2 - d1)
+                .collect::<Vec<f64>>()
+        })
+        .collect()
+}
+
+/// M2 positioners controller
+pub struct Positioners<F> {
+    filters: Vec<F>,
+    cmd: Vec<f64>,
+    nodes: Vec<f64>,
+    forces: Vec<f64>,
+    rbm2pos: Option<RigidBodyToPositioners>,
+}
+impl<F: Filter + Clone> Positioners<F> {
+    /// Creates the M2 positioners controller, the same `filter` is applied to each positioner
+    pub fn new(filter: F) -> Self {
+        Self {
+            filters: vec![filter; N_POSITIONER],
+            cmd: vec![0f64; N_POSITIONER],
+            nodes: vec![0f64; 2 * N_POSITIONER],
+            forces: vec![0f64; N_POSITIONER],
+            rbm2pos: None,
+        }
+    }
+    /// Sets the transformation of the rigid body motions commands [`M2RBMCmd`](crate::io::IO::M2RBMCmd) into positioners commands
+    pub fn rigid_body(self, rbm2pos: RigidBodyToPositioners) -> Self {
+        Self {
+            rbm2pos: Some(rbm2pos),
+            ..self
+        }
+    }
+    /// Returns the elongation of the 42 positioners
+    pub fn elongations(&self) -> Vec<f64> {
+        elongations(&self.nodes)
+    }
+    /// Returns the filters, one per positioner
+    pub fn filters(&self) -> &[F] {
+        &self.filters
+    }
+}
+impl<F: Filter + Clone> Iterator for Positioners<F> {
+    type Item = ();
+    fn next(&mut self) -> Option<Self::Item> {
+        self.forces = self
+            .filters
+            .iter_mut()
+            .zip(self.cmd.iter())
+            .zip(elongations(&self.nodes))
+            .map(|((f, c), e)| f.update(c - e))
+            .collect();
+        Some(())
+    }
+}
+/// The positioners commands [`M2PosCmd`](crate::io::IO::M2PosCmd) and [`M2RBMCmd`](crate::io::IO::M2RBMCmd) are optional inputs, they are not listed in the inputs tags
+impl<F> IOTags for Positioners<F> {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        vec![jar::MCM2SmHexF::new()]
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        vec![jar::MCM2SmHexD::new()]
+    }
+}
+impl<F: Filter + Clone> DOS for Positioners<F> {
+    fn inputs(
+        &mut self,
+        data: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        let mut nodes = None;
+        for io in data {
+            match io {
+                IO::M2PosCmd { .. } => {
+                    let cmd = std::result::Result::<Vec<f64>, DOSError<IOError>>::from(io)?;
+                    if cmd.len() != N_POSITIONER {
+                        return Err(DOSError::Component(PositionersError::Size(cmd.len())).into());
+                    }
+                    self.cmd = cmd;
+                }
+                IO::M2RBMCmd { .. } => {
+                    let rbm = std::result::Result::<Vec<f64>, DOSError<IOError>>::from(io)?;
+                    self.cmd = self
+                        .rbm2pos
+                        .as_ref()
+                        .ok_or(DOSError::Component(PositionersError::RigidBody))?
+                        .positioners(&rbm)?;
+                }
+                IO::MCM2SmHexD { .. } => {
+                    nodes = Some(std::result::Result::<Vec<f64>, DOSError<IOError>>::from(
+                        io,
+                    )?);
+                }
+                _ => (),
+            }
+        }
+        match nodes {
+            Some(nodes) if nodes.len() == 2 * N_POSITIONER => {
+                self.nodes = nodes;
+                Ok(self)
+            }
+            Some(nodes) => Err(DOSError::Component(PositionersError::Size(nodes.len())).into()),
+            None => {
+                Err(DOSError::Component(PositionersError::Input(jar::MCM2SmHexD::new())).into())
+            }
+        }
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        Some(vec![jar::MCM2SmHexF::with(
+            self.forces
+                .chunks(6)
+                .flat_map(|f| f.iter().map(|f| -f).chain(f.iter().cloned()))
+                .collect(),
+        )])
+    }
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        self.filters.iter_mut().for_each(|f| f.reset());
+        self.cmd.iter_mut().for_each(|x| *x = 0f64);
+        self.nodes.iter_mut().for_each(|x| *x = 0f64);
+        self.forces.iter_mut().for_each(|x| *x = 0f64);
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controllers::linear::Pid;
+
+    #[test]
+    fn m2_positioners() {
+        let mut m2_pos = Positioners::new(Pid::new(1e3, 2., 0., 0.));
+        assert!(m2_pos
+            .inputs(vec![jar::MCM2SmHexD::with(vec![0f64; 42])])
+            .is_err());
+        let mut nodes = vec![0f64; 84];
+        nodes[6] = 1.;
+        let mut cmd = vec![0f64; 42];
+        cmd[41] = 1.;
+        let y = m2_pos
+            .in_step_out(vec![jar::M2PosCmd::with(cmd), jar::MCM2SmHexD::with(nodes)])
+            .unwrap()
+            .unwrap();
+        let forces = Option::<Vec<f64>>::from(&y[0]).unwrap();
+        assert_eq!(forces.len(), 84);
+        assert_eq!((forces[0], forces[6]), (2., -2.));
+        assert_eq!((forces[77], forces[83]), (-2., 2.));
+        assert_eq!(forces.iter().filter(|f| **f != 0.).count(), 4);
+    }
+
+    #[test]
+    fn m2_positioners_layout() {
+        // segment #2, positioner #3: first end node 12+2, second end node 12+6+2
+        let mut nodes = vec![0f64; 84];
+        nodes[14] = 1.;
+        nodes[20] = 3.;
+        let e = elongations(&nodes);
+        assert_eq!(e[8], 2.);
+        assert_eq!(e.iter().filter(|e| **e != 0.).count(), 1);
+    }
+
+    #[test]
+    fn m2_positioners_rigid_body() {
+        let mut rbm = vec![0f64; 42];
+        rbm[8] = 1.;
+        let mut m2_pos = Positioners::new(Pid::new(1e3, 1., 0., 0.));
+        assert!(m2_pos
+            .inputs(vec![
+                jar::M2RBMCmd::with(rbm.clone()),
+                jar::MCM2SmHexD::with(vec![0f64; 84])
+            ])
+            .is_err());
+        let geometry = [[([1., 0., 0.], [0., 0., 1.]); 6]; 7];
+        let rbm2pos = RigidBodyToPositioners::from_geometry(&geometry).unwrap();
+        assert!(rbm2pos.positioners(&rbm[..6]).is_err());
+        let mut m2_pos = m2_pos.rigid_body(rbm2pos);
+        let y = m2_pos
+            .in_step_out(vec![
+                jar::M2RBMCmd::with(rbm),
+                jar::MCM2SmHexD::with(vec![0f64; 84]),
+            ])
+            .unwrap()
+            .unwrap();
+        let forces = Option::<Vec<f64>>::from(&y[0]).unwrap();
+        assert!(forces[12..18].iter().all(|f| *f == -1.));
+        assert!(forces[18..24].iter().all(|f| *f == 1.));
+        assert_eq!(forces.iter().filter(|f| **f != 0.).count(), 12);
+    }
+}
@@ -1,5 +1,7 @@
+pub mod linear;
 pub mod m1;
+pub mod m2;
 pub mod mount;
 pub mod simulink;
-pub use simulink::Simulink;
+pub use simulink::{Simulink, SimulinkParameters, SimulinkStates};
 pub mod state_space;
@@ -1,10 +1,11 @@
 use crate::{
-    build_controller, build_inputs, build_outputs, import_simulink,
+    build_controller, build_inputs, build_outputs,
+    controllers::SimulinkStates,
     io::{jar, Tags},
     IOTags, DOS, IO,
 };
 
-import_simulink!(MountControl0, U : (Mount_SP,3,Mount_FB,20), Y : (Mount_cmd,3));
+include!(concat!(env!("OUT_DIR"), "/src/controllers/mount/controller/import_simulink.rs"));
 build_inputs!(
     SP,
     3,
@@ -21,15 +22,19 @@ build_inputs!(
 );
 build_outputs!(CMD, 3);
 build_controller!(MountControl0,
-                  U : (Mount_FB -> (OssAzDrive,oss_az_drive),
+                  U : (Mount_SP -> (SP,sp),
+                       Mount_FB -> (OssAzDrive,oss_az_drive),
                        Mount_FB -> (OssElDrive,oss_el_drive),
                        Mount_FB -> (OssGirDrive,oss_gir_drive)),
                   Y : (Mount_cmd -> (CMD,cmd))
 );
 
+/// The mount setpoint [`MountSP`](crate::io::IO::MountSP) is an optional input, it is not listed in the inputs tags and it is set to 0 by default
 impl<'a> IOTags for Controller<'a> {
     fn outputs_tags(&self) -> Vec<Tags> {
-        vec![jar::MountCmd::new()]
+        let mut tags = vec![jar::MountCmd::new()];
+        tags.extend(self.states_tags());
+        tags
     }
     fn inputs_tags(&self) -> Vec<Tags> {
         vec![
@@ -40,9 +45,18 @@ impl<'a> IOTags for Controller<'a> {
     }
 }
 impl<'a> DOS for Controller<'a> {
+    fn reset(&mut self) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        Simulink::reset(self);
+        Ok(self)
+    }
     fn inputs(&mut self, data: Vec<IO<Vec<f64>>>) -> Result<&mut Self, Box<dyn std::error::Error>> {
         if data.into_iter().fold(3, |mut a, io| {
             match io {
+                IO::MountSP { data: Some(values) } => {
+                    for (k, v) in values.into_iter().enumerate() {
+                        self.sp[k] = v;
+                    }
+                }
                 IO::OSSAzDriveD { data: Some(values) } => {
                     for (k, v) in values.into_iter().enumerate() {
                         self.oss_az_drive[k] = v;
@@ -75,8 +89,10 @@ impl<'a> DOS for Controller<'a> {
         }
     }
     fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
-        Some(vec![IO::MountCmd {
+        let mut y = vec![IO::MountCmd {
             data: Some(Vec::<f64>::from(&self.cmd)),
-        }])
+        }];
+        y.extend(self.states_outputs());
+        Some(y)
     }
 }
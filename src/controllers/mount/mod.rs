@@ -1,3 +1,9 @@
+#[cfg(feature = "mount-baseline")]
 pub mod controller;
+#[cfg(feature = "mount-baseline")]
 pub mod drives;
+#[cfg(feature = "mount-pdr")]
 pub mod pdr;
+pub mod trajectory;
+#[cfg(any(feature = "mount-baseline", feature = "mount-pdr"))]
+pub mod system;
@@ -1,10 +1,11 @@
 use crate::{
-    build_controller, build_inputs, build_outputs, import_simulink,
+    build_controller, build_inputs, build_outputs,
+    controllers::SimulinkStates,
     io::{jar, Tags},
     IOTags, DOS, IO,
 };
 
-import_simulink!(Mount_Control, U : (Mount_SP,3,Mount_FB,14), Y : (Mount_cmd,3));
+include!(concat!(env!("OUT_DIR"), "/src/controllers/mount/pdr/controller/import_simulink.rs"));
 build_inputs!(
     SP,
     3,
@@ -21,15 +22,19 @@ build_inputs!(
 );
 build_outputs!(MountCmd, 3);
 build_controller!(Mount_Control,
-                  U : (Mount_FB -> (OssAzDrive,oss_az_drive),
+                  U : (Mount_SP -> (SP,sp),
+                       Mount_FB -> (OssAzDrive,oss_az_drive),
                        Mount_FB -> (OssElDrive,oss_el_drive),
                        Mount_FB -> (OssGirDrive,oss_gir_drive)),
                   Y : (Mount_cmd -> (MountCmd,cmd))
 );
 
+/// The mount setpoint [`MountSP`](crate::io::IO::MountSP) is an optional input, it is not listed in the inputs tags and it is set to 0 by default
 impl<'a> IOTags for Controller<'a> {
     fn outputs_tags(&self) -> Vec<Tags> {
-        vec![jar::MountCmd::new()]
+        let mut tags = vec![jar::MountCmd::new()];
+        tags.extend(self.states_tags());
+        tags
     }
     fn inputs_tags(&self) -> Vec<Tags> {
         vec![
@@ -40,9 +45,18 @@ impl<'a> IOTags for Controller<'a> {
     }
 }
 impl<'a> DOS for Controller<'a> {
+    fn reset(&mut self) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        Simulink::reset(self);
+        Ok(self)
+    }
     fn inputs(&mut self, data: Vec<IO<Vec<f64>>>) -> Result<&mut Self, Box<dyn std::error::Error>> {
         if data.into_iter().fold(3, |mut a, io| {
             match io {
+                IO::MountSP { data: Some(values) } => {
+                    for (k, v) in values.into_iter().enumerate() {
+                        self.sp[k] = v;
+                    }
+                }
                 IO::OSSAzEncoderAngle { data: Some(values) } => {
                     for (k, v) in values.into_iter().enumerate() {
                         self.oss_az_drive[k] = v;
@@ -75,9 +89,11 @@ impl<'a> DOS for Controller<'a> {
         }
     }
     fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
-        Some(vec![IO::MountCmd {
+        let mut y = vec![IO::MountCmd {
             data: Some(Vec::<f64>::from(&self.cmd)),
-        }])
+        }];
+        y.extend(self.states_outputs());
+        Some(y)
     }
 }
 
@@ -87,6 +103,7 @@ mod tests {
 
     #[test]
     fn pdr_mount_control_zeros_test() {
+        let _lock = crate::controllers::simulink::test_lock();
         let mut mnt_ctrl = Controller::new();
         let u = vec![
             jar::OSSAzEncoderAngle::with(vec![0f64; 8]),
@@ -99,8 +116,9 @@ mod tests {
 
     #[test]
     fn pdr_mount_control_ones_test() {
+        let _lock = crate::controllers::simulink::test_lock();
         let mut mnt_ctrl = Controller::new();
-        for k in 0..5 {
+        for _ in 0..5 {
             let u = vec![
                 jar::OSSAzEncoderAngle::with(vec![1f64; 8]),
                 jar::OSSElEncoderAngle::with(vec![1f64; 8]),
@@ -110,4 +128,48 @@ mod tests {
             println!("PDR MOUNT CONTROL ONES TEST: {:#?}", y);
         }
     }
+
+    #[test]
+    fn pdr_mount_control_parameters_test() {
+        use crate::controllers::SimulinkParameters;
+        let _lock = crate::controllers::simulink::test_lock();
+        let mut mnt_ctrl = Controller::new();
+        let parameters = mnt_ctrl.parameters();
+        assert!(mnt_ctrl.parameter("DiscreteSS_Z").is_none());
+        let c = mnt_ctrl.parameter("DiscreteSS_C").unwrap();
+        assert_eq!(c.len(), 11);
+        assert!(mnt_ctrl.set_parameter("DiscreteSS_C", &[0f64; 10]).is_err());
+        let c2: Vec<f64> = c.iter().map(|x| 2. * x).collect();
+        mnt_ctrl.set_parameter("DiscreteSS_C", &c2).unwrap();
+        assert_eq!(mnt_ctrl.parameter("DiscreteSS_C").unwrap(), c2);
+        assert_eq!(mnt_ctrl.parameters().len(), 6);
+        mnt_ctrl.set_parameters(&parameters).unwrap();
+        assert_eq!(mnt_ctrl.parameters(), parameters);
+    }
+
+    #[test]
+    fn pdr_mount_control_states_test() {
+        let _lock = crate::controllers::simulink::test_lock();
+        let mut mnt_ctrl = Controller::new();
+        assert!(mnt_ctrl
+            .log_states(jar::MountCtrlStates::new(), &["DW.DiscreteSS_Z"])
+            .is_err());
+        mnt_ctrl
+            .log_states(
+                jar::MountCtrlStates::new(),
+                &["DW.DiscreteSS_DSTATE", "B.RTout"],
+            )
+            .unwrap();
+        let u = vec![
+            jar::OSSAzEncoderAngle::with(vec![1f64; 8]),
+            jar::OSSElEncoderAngle::with(vec![1f64; 8]),
+            jar::OSSRotEncoderAngle::with(vec![1f64; 4]),
+        ];
+        let y = mnt_ctrl.in_step_out(u).unwrap().unwrap();
+        assert_eq!(y.len(), 2);
+        assert!(y.iter().zip(mnt_ctrl.outputs_tags()).all(|(y, t)| *y == t));
+        let states = Option::<Vec<f64>>::from(&y[1]).unwrap();
+        assert_eq!(states.len(), 12);
+        assert_eq!(states[..11].to_vec(), mnt_ctrl.state("DW.DiscreteSS_DSTATE").unwrap());
+    }
 }
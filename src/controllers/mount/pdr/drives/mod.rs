@@ -1,10 +1,11 @@
 use crate::{
-    build_controller, build_inputs, build_outputs, import_simulink,
+    build_controller, build_inputs, build_outputs,
+    controllers::SimulinkStates,
     io::{jar, Tags},
     IOTags, DOS, IO,
 };
 
-import_simulink!(Mount_Drv_PDR2021, U : (Mount_cmd,3,Mount_pos,14), Y : (Mount_F,20));
+include!(concat!(env!("OUT_DIR"), "/src/controllers/mount/pdr/drives/import_simulink.rs"));
 build_inputs!(
     MountCmd,
     3,
@@ -46,11 +47,13 @@ build_controller!(Mount_Drv_PDR2021,
 // Mount
 impl<'a> IOTags for Controller<'a> {
     fn outputs_tags(&self) -> Vec<Tags> {
-        vec![
+        let mut tags = vec![
             jar::OSSAzDriveTorque::new(),
             jar::OSSElDriveTorque::new(),
             jar::OSSRotDriveTorque::new(),
-        ]
+        ];
+        tags.extend(self.states_tags());
+        tags
     }
     fn inputs_tags(&self) -> Vec<Tags> {
         vec![
@@ -62,6 +65,10 @@ impl<'a> IOTags for Controller<'a> {
     }
 }
 impl<'a> DOS for Controller<'a> {
+    fn reset(&mut self) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        Simulink::reset(self);
+        Ok(self)
+    }
     fn inputs(&mut self, data: Vec<IO<Vec<f64>>>) -> Result<&mut Self, Box<dyn std::error::Error>> {
         if data.into_iter().fold(4, |mut a, io| {
             match io {
@@ -103,7 +110,7 @@ impl<'a> DOS for Controller<'a> {
         }
     }
     fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
-        Some(vec![
+        let mut y = vec![
             IO::OSSAzDriveTorque {
                 data: Some(Vec::<f64>::from(&self.oss_az_drive_f)),
             },
@@ -113,7 +120,9 @@ impl<'a> DOS for Controller<'a> {
             IO::OSSRotDriveTorque {
                 data: Some(Vec::<f64>::from(&self.oss_gir_drive_f)),
             },
-        ])
+        ];
+        y.extend(self.states_outputs());
+        Some(y)
     }
 }
 
@@ -123,6 +132,7 @@ mod tests {
 
     #[test]
     fn pdr_mount_drive_zeros() {
+        let _lock = crate::controllers::simulink::test_lock();
         let mut mnt_drives = Controller::new();
         for _ in 0..5 {
             let u = vec![
@@ -138,6 +148,7 @@ mod tests {
 
     #[test]
     fn pdr_mount_drive_ones() {
+        let _lock = crate::controllers::simulink::test_lock();
         let mut mnt_drives = Controller::new();
         for _ in 0..5 {
             let u = vec![
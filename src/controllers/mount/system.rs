@@ -0,0 +1,266 @@
+//! Mount control system
+//!
+//! The mount control system is the mount controller followed by the mount drives, either the baseline models (`controller` and `drives`)
+//! or the PDR models (`pdr::controller` and `pdr::drives`), each compiled with its cargo feature.
+//! The two versions have different inputs and outputs:
+//!
+//! | model | inputs | outputs |
+//! |-------|--------|---------|
+//! | baseline | `OSSAzDriveD`, `OSSElDriveD`, `OSSGIRDriveD` | `OSSAzDriveF`, `OSSElDriveF`, `OSSGIRDriveF` |
+//! | PDR | `OSSAzEncoderAngle`, `OSSElEncoderAngle`, `OSSRotEncoderAngle` | `OSSAzDriveTorque`, `OSSElDriveTorque`, `OSSRotDriveTorque` |
+//!
+//! so the FEM inputs and outputs are set from the [`MountSystem`] tags.
+//! The mount setpoint [`MountSP`](crate::io::IO::MountSP) is an optional input of both versions.
+//! The signals and states of the controller and of the drives logged with [`MountSystem::log_controller_states`] and [`MountSystem::log_drives_states`]
+//! are appended to the outputs and to the outputs tags.
+//!
+//! # Example
+//! The mount model is selected at runtime with the `MOUNT_MODEL` environment variable, either `baseline` or `pdr`
+//! ```no_run
+//! use dos::{
+//!     controllers::{mount::system::MountSystem, state_space::DiscreteStateSpace},
+//!     DOS,
+//! };
+//! use fem::FEM;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let model = std::env::var("MOUNT_MODEL").unwrap_or_else(|_| "pdr".to_owned());
+//!     let mut mount = MountSystem::new(model.parse()?)?;
+//!     let mut fem = DiscreteStateSpace::from(FEM::from_pickle("modal_state_space_model_2ndOrder.pkl")?)
+//!         .sampling(1e3)
+//!         .inputs_from(&mount)
+//!         .outputs_to(&mount)
+//!         .build()?;
+//!     let mut mount_forces = mount.outputs();
+//!     while let Some(fem_forces) = mount_forces {
+//!         let encoders = fem.in_step_out(fem_forces)?.ok_or("FEM output is empty")?;
+//!         mount_forces = mount.in_step_out(encoders)?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+#[cfg(feature = "mount-pdr")]
+use super::pdr;
+#[cfg(feature = "mount-baseline")]
+use super::{controller, drives};
+use crate::{controllers::SimulinkStates, io::Tags, DOSError, IOTags, DOS, IO};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum MountError {
+    /// Unknown mount model name
+    Unknown(String),
+    /// Mount model not compiled, see the cargo features
+    Disabled(MountModel),
+}
+type Result<T> = std::result::Result<T, DOSError<MountError>>;
+
+/// Mount control models
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MountModel {
+    /// Baseline models of 2020
+    Baseline,
+    /// Models of the preliminary design review of 2021
+    Pdr,
+}
+impl FromStr for MountModel {
+    type Err = DOSError<MountError>;
+    /// Parses either `baseline` or `pdr`, case insensitive
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "baseline" => Ok(MountModel::Baseline),
+            "pdr" => Ok(MountModel::Pdr),
+            _ => Err(DOSError::Component(MountError::Unknown(s.to_owned()))),
+        }
+    }
+}
+impl fmt::Display for MountModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MountModel::Baseline => write!(f, "baseline"),
+            MountModel::Pdr => write!(f, "pdr"),
+        }
+    }
+}
+
+enum Models<'a> {
+    #[cfg(feature = "mount-baseline")]
+    Baseline(controller::Controller<'a>, drives::Controller<'a>),
+    #[cfg(feature = "mount-pdr")]
+    Pdr(pdr::controller::Controller<'a>, pdr::drives::Controller<'a>),
+}
+
+/// Mount controller and drives
+pub struct MountSystem<'a> {
+    model: MountModel,
+    models: Models<'a>,
+    encoders: Vec<IO<Vec<f64>>>,
+    command: Option<Vec<IO<Vec<f64>>>>,
+}
+/// Applies the same expression to the controller and to the drives of either model
+macro_rules! dispatch {
+    ($models:expr, ($ctrl:ident, $drives:ident) => $e:expr) => {
+        match $models {
+            #[cfg(feature = "mount-baseline")]
+            Models::Baseline($ctrl, $drives) => $e,
+            #[cfg(feature = "mount-pdr")]
+            Models::Pdr($ctrl, $drives) => $e,
+        }
+    };
+}
+impl<'a> MountSystem<'a> {
+    /// Creates the mount control system of the given model
+    pub fn new(model: MountModel) -> Result<Self> {
+        let models = match model {
+            #[cfg(feature = "mount-baseline")]
+            MountModel::Baseline => {
+                Models::Baseline(controller::Controller::new(), drives::Controller::new())
+            }
+            #[cfg(feature = "mount-pdr")]
+            MountModel::Pdr => Models::Pdr(
+                pdr::controller::Controller::new(),
+                pdr::drives::Controller::new(),
+            ),
+            #[allow(unreachable_patterns)]
+            _ => return Err(DOSError::Component(MountError::Disabled(model))),
+        };
+        Ok(Self {
+            model,
+            models,
+            encoders: vec![],
+            command: None,
+        })
+    }
+    /// Returns the mount model
+    pub fn model(&self) -> MountModel {
+        self.model
+    }
+    /// Returns the last mount controller outputs, the drives command
+    pub fn command(&self) -> Option<&[IO<Vec<f64>>]> {
+        self.command.as_deref()
+    }
+    /// Appends the mount controller signals and states `names` to the outputs, see [`SimulinkStates::log_states`]
+    pub fn log_controller_states(
+        &mut self,
+        tag: Tags,
+        names: &[&str],
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        dispatch!(&mut self.models, (ctrl, _drives) => ctrl.log_states(tag, names).map(|_| ()))?;
+        Ok(self)
+    }
+    /// Appends the mount drives signals and states `names` to the outputs, see [`SimulinkStates::log_states`]
+    pub fn log_drives_states(
+        &mut self,
+        tag: Tags,
+        names: &[&str],
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        dispatch!(&mut self.models, (_ctrl, drives) => drives.log_states(tag, names).map(|_| ()))?;
+        Ok(self)
+    }
+}
+impl<'a> IOTags for MountSystem<'a> {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        dispatch!(&self.models, (ctrl, drives) => {
+            let mut tags = drives.outputs_tags();
+            tags.extend(ctrl.states_tags());
+            tags
+        })
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        dispatch!(&self.models, (ctrl, _drives) => ctrl.inputs_tags())
+    }
+}
+impl<'a> Iterator for MountSystem<'a> {
+    type Item = ();
+    fn next(&mut self) -> Option<Self::Item> {
+        let encoders = &self.encoders;
+        let command = &mut self.command;
+        dispatch!(&mut self.models, (ctrl, drives) => {
+            ctrl.next()?;
+            let mut u = ctrl.outputs()?;
+            *command = Some(u.clone());
+            u.extend_from_slice(encoders);
+            match drives.inputs(u) {
+                Ok(drives) => drives.next(),
+                Err(e) => {
+                    log::error!("Mount drives inputs failed: {}", e);
+                    None
+                }
+            }
+        })
+    }
+}
+impl<'a> DOS for MountSystem<'a> {
+    fn inputs(
+        &mut self,
+        data: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        let tags = self.inputs_tags();
+        self.encoders = data
+            .iter()
+            .filter(|x| tags.iter().any(|t| *x == t))
+            .cloned()
+            .collect();
+        dispatch!(&mut self.models, (ctrl, _drives) => ctrl.inputs(data).map(|_| ()))?;
+        Ok(self)
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        dispatch!(&mut self.models, (ctrl, drives) => {
+            let mut y = drives.outputs()?;
+            y.extend(ctrl.states_outputs());
+            Some(y)
+        })
+    }
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        dispatch!(&mut self.models, (ctrl, drives) => {
+            ctrl.reset()?;
+            drives.reset().map(|_| ())
+        })?;
+        self.encoders.clear();
+        self.command = None;
+        Ok(self)
+    }
+}
+
+#[cfg(all(test, feature = "mount-baseline", feature = "mount-pdr"))]
+mod tests {
+    use super::*;
+    use crate::io::jar;
+
+    #[test]
+    fn mount_system_models() {
+        let _lock = crate::controllers::simulink::test_lock();
+        for model in &["baseline", "pdr"] {
+            let mut mount = MountSystem::new(model.parse().unwrap()).unwrap();
+            let u: Vec<IO<Vec<f64>>> = mount
+                .inputs_tags()
+                .iter()
+                .zip(vec![8, 8, 4])
+                .map(|(t, n)| IO::<Vec<f64>>::from((t, vec![1f64; n])))
+                .collect();
+            let y = mount.in_step_out(u).unwrap().unwrap();
+            assert_eq!(mount.command().unwrap().len(), 1);
+            assert!(y.iter().zip(mount.outputs_tags()).all(|(y, t)| *y == t));
+        }
+        let mut mount = MountSystem::new(MountModel::Pdr).unwrap();
+        mount
+            .log_controller_states(jar::MountCtrlStates::new(), &["DW.DiscreteSS_DSTATE"])
+            .unwrap();
+        let tags = mount.outputs_tags();
+        assert_eq!(tags.len(), 4);
+        assert!(tags[3] == jar::MountCtrlStates::new::<()>());
+        let u: Vec<IO<Vec<f64>>> = mount
+            .inputs_tags()
+            .iter()
+            .zip(vec![8, 8, 4])
+            .map(|(t, n)| IO::<Vec<f64>>::from((t, vec![1f64; n])))
+            .collect();
+        let y = mount.in_step_out(u).unwrap().unwrap();
+        assert_eq!(y.len(), 4);
+        assert!(y.iter().zip(tags).all(|(y, t)| *y == t));
+        assert_eq!(Option::<Vec<f64>>::from(&y[3]).unwrap().len(), 11);
+        assert!("mount".parse::<MountModel>().is_err());
+    }
+}
@@ -0,0 +1,382 @@
+//! Mount trajectory generator
+//!
+//! The [`TrajectoryGenerator`] source outputs the azimuth, elevation and rotator setpoints [`MountSP`](crate::io::IO::MountSP) of the mount controller.
+//! A trajectory is a sequence of segments built with [`Trajectory`]:
+//!  - a hold, the setpoints are constant,
+//!  - a slew to a new position, each axis follows a time optimal profile within its velocity, acceleration and jerk [`Limits`] (S-curve profile),
+//!  - a sidereal tracking, the setpoints follow the motion of a star (see [`Tracking`]).
+//!
+//! All angles are in radians.
+//!
+//! # Example
+//! A 1° azimuth slew followed by 10s of tracking
+//! ```no_run
+//! use dos::{
+//!     controllers::mount::trajectory::{Limits, Tracking, Trajectory},
+//!     DOS,
+//! };
+//!
+//! let limits = Limits::new(2f64.to_radians(), 0.75f64.to_radians(), 0.5f64.to_radians());
+//! let mut setpoints = Trajectory::new(1e3, [limits; 3])
+//!     .slew([1f64.to_radians(), 0., 0.])
+//!     .track(Tracking::new(0f64.to_radians(), (-60f64).to_radians(), 10.0))
+//!     .build();
+//! while let Some(sp) = setpoints.outputs() {
+//!     // the setpoints are passed to the mount controller together with the mount feedback
+//! }
+//! ```
+
+use crate::{
+    io::{jar, Tags},
+    IOTags, DOS, IO,
+};
+use std::f64::consts::PI;
+
+/// Earth sidereal rotation rate in rad/s
+pub const SIDEREAL_RATE: f64 = 7.292_115_9e-5;
+/// Latitude of the GMT site at Las Campanas Observatory in radians
+pub const GMT_LATITUDE: f64 = -0.506_40;
+
+/// Axis motion limits
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Velocity limit in rad/s
+    pub velocity: f64,
+    /// Acceleration limit in rad/s<sup>2</sup>
+    pub acceleration: f64,
+    /// Jerk limit in rad/s<sup>3</sup>
+    pub jerk: f64,
+}
+impl Limits {
+    /// Creates new limits
+    pub fn new(velocity: f64, acceleration: f64, jerk: f64) -> Self {
+        Self {
+            velocity,
+            acceleration,
+            jerk,
+        }
+    }
+}
+
+/// Sidereal tracking
+#[derive(Debug, Clone, Copy)]
+pub struct Tracking {
+    /// Hour angle of the star at the beginning of the tracking
+    pub hour_angle: f64,
+    /// Declination of the star
+    pub declination: f64,
+    /// Latitude of the telescope
+    pub latitude: f64,
+    /// Tracking duration in s
+    pub duration: f64,
+}
+impl Tracking {
+    /// Creates a new tracking of a star at `declination` from `hour_angle` for `duration` seconds at the GMT latitude
+    pub fn new(hour_angle: f64, declination: f64, duration: f64) -> Self {
+        Self {
+            hour_angle,
+            declination,
+            latitude: GMT_LATITUDE,
+            duration,
+        }
+    }
+    /// Sets the latitude of the telescope
+    pub fn latitude(self, latitude: f64) -> Self {
+        Self { latitude, ..self }
+    }
+    /// Returns the azimuth, the elevation and the parallactic angle of the star `t` seconds after the beginning of the tracking
+    ///
+    /// The azimuth is measured from North toward East
+    pub fn pointing(&self, t: f64) -> [f64; 3] {
+        let h = self.hour_angle + SIDEREAL_RATE * t;
+        let (sin_d, cos_d) = self.declination.sin_cos();
+        let (sin_l, cos_l) = self.latitude.sin_cos();
+        let (sin_h, cos_h) = h.sin_cos();
+        let el = (sin_l * sin_d + cos_l * cos_d * cos_h).asin();
+        let az = (-cos_d * sin_h).atan2(sin_d * cos_l - cos_d * sin_l * cos_h);
+        let q = sin_h.atan2(self.latitude.tan() * cos_d - sin_d * cos_h);
+        [az, el, q]
+    }
+}
+
+/// Jerk limited rest to rest motion of a single axis, a sequence of `(duration,jerk)` phases
+#[derive(Debug, Clone, Default)]
+struct SCurve {
+    phases: Vec<(f64, f64)>,
+}
+impl SCurve {
+    fn new(distance: f64, limits: &Limits) -> Self {
+        let d = distance.abs();
+        if d == 0f64 {
+            return Default::default();
+        }
+        let Limits {
+            velocity,
+            acceleration,
+            jerk,
+        } = *limits;
+        // durations of the jerk phase and of the whole acceleration phase for the peak velocity `v`
+        let accel = |v: f64| {
+            if v * jerk < acceleration * acceleration {
+                let tj = (v / jerk).sqrt();
+                (tj, 2. * tj)
+            } else {
+                let tj = acceleration / jerk;
+                (tj, tj + v / acceleration)
+            }
+        };
+        let v = if velocity * accel(velocity).1 <= d {
+            velocity
+        } else {
+            // the peak velocity is not reached: bisection on d = v * Ta(v)
+            let (mut lo, mut hi) = (0f64, velocity);
+            for _ in 0..100 {
+                let v = 0.5 * (lo + hi);
+                if v * accel(v).1 > d {
+                    hi = v;
+                } else {
+                    lo = v;
+                }
+            }
+            lo
+        };
+        let (tj, ta) = accel(v);
+        let tv = (d / v - ta).max(0f64);
+        let j = jerk * distance.signum();
+        Self {
+            phases: vec![
+                (tj, j),
+                (ta - 2. * tj, 0.),
+                (tj, -j),
+                (tv, 0.),
+                (tj, -j),
+                (ta - 2. * tj, 0.),
+                (tj, j),
+            ],
+        }
+    }
+    fn duration(&self) -> f64 {
+        self.phases.iter().map(|(t, _)| t).sum()
+    }
+    /// Returns the position `t` seconds after the beginning of the motion
+    fn position(&self, t: f64) -> f64 {
+        let (mut p, mut v, mut a) = (0f64, 0f64, 0f64);
+        let mut t = t.max(0f64);
+        for &(duration, j) in &self.phases {
+            let dt = t.min(duration);
+            p += v * dt + a * dt * dt / 2. + j * dt * dt * dt / 6.;
+            v += a * dt + j * dt * dt / 2.;
+            a += j * dt;
+            t -= dt;
+            if t <= 0f64 {
+                break;
+            }
+        }
+        p
+    }
+}
+
+/// Trajectory segment
+#[derive(Debug, Clone)]
+enum Segment {
+    Hold(f64),
+    Slew([SCurve; 3]),
+    Track(Tracking),
+}
+impl Segment {
+    fn duration(&self) -> f64 {
+        match self {
+            Segment::Hold(duration) => *duration,
+            Segment::Slew(axes) => axes.iter().map(|x| x.duration()).fold(0f64, f64::max),
+            Segment::Track(tracking) => tracking.duration,
+        }
+    }
+    /// Returns the setpoints offsets from the beginning of the segment
+    fn delta(&self, t: f64) -> [f64; 3] {
+        match self {
+            Segment::Hold(_) => [0f64; 3],
+            Segment::Slew(axes) => [
+                axes[0].position(t),
+                axes[1].position(t),
+                axes[2].position(t),
+            ],
+            Segment::Track(tracking) => {
+                let p0 = tracking.pointing(0f64);
+                let p = tracking.pointing(t);
+                let mut delta = [0f64; 3];
+                for ((delta, p), p0) in delta.iter_mut().zip(p.iter()).zip(p0.iter()) {
+                    // wrapped into [-pi,pi]
+                    *delta = (p - p0 + PI).rem_euclid(2. * PI) - PI;
+                }
+                delta
+            }
+        }
+    }
+}
+
+/// Mount trajectory builder
+pub struct Trajectory {
+    sampling_rate: f64,
+    limits: [Limits; 3],
+    start: [f64; 3],
+    position: [f64; 3],
+    segments: Vec<(f64, [f64; 3], Segment)>,
+    duration: f64,
+}
+impl Trajectory {
+    /// Creates a new trajectory builder with the `sampling_rate` in Hz and the azimuth, elevation and rotator motion `limits`
+    pub fn new(sampling_rate: f64, limits: [Limits; 3]) -> Self {
+        Self {
+            sampling_rate,
+            limits,
+            start: [0f64; 3],
+            position: [0f64; 3],
+            segments: vec![],
+            duration: 0f64,
+        }
+    }
+    /// Sets the initial setpoints, 0 by default
+    ///
+    /// It must be called before adding any segment
+    pub fn start(self, start: [f64; 3]) -> Self {
+        assert!(
+            self.segments.is_empty(),
+            "start must be set before adding trajectory segments"
+        );
+        Self {
+            start,
+            position: start,
+            ..self
+        }
+    }
+    fn push(mut self, segment: Segment) -> Self {
+        let duration = segment.duration();
+        let end = segment.delta(duration);
+        let position = self.position;
+        self.segments.push((self.duration, position, segment));
+        self.duration += duration;
+        self.position
+            .iter_mut()
+            .zip(end.iter())
+            .for_each(|(p, e)| *p += e);
+        self
+    }
+    /// Holds the setpoints for `duration` seconds
+    pub fn hold(self, duration: f64) -> Self {
+        self.push(Segment::Hold(duration))
+    }
+    /// Slews from the current setpoints to the `target` setpoints
+    pub fn slew(self, target: [f64; 3]) -> Self {
+        let axes = [
+            SCurve::new(target[0] - self.position[0], &self.limits[0]),
+            SCurve::new(target[1] - self.position[1], &self.limits[1]),
+            SCurve::new(target[2] - self.position[2], &self.limits[2]),
+        ];
+        self.push(Segment::Slew(axes))
+    }
+    /// Tracks a star from the current setpoints
+    ///
+    /// The setpoints follow the changes in azimuth, elevation and parallactic angle of the star from the beginning of the tracking
+    pub fn track(self, tracking: Tracking) -> Self {
+        self.push(Segment::Track(tracking))
+    }
+    /// Returns the trajectory duration in s
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+    /// Builds a trajectory generator source object
+    pub fn build(self) -> TrajectoryGenerator {
+        TrajectoryGenerator {
+            sampling_rate: self.sampling_rate,
+            n_sample: (self.duration * self.sampling_rate).ceil() as usize + 1,
+            step: 0,
+            start: self.start,
+            segments: self.segments,
+        }
+    }
+}
+
+/// Mount trajectory generator source
+pub struct TrajectoryGenerator {
+    pub sampling_rate: f64,
+    pub n_sample: usize,
+    step: usize,
+    start: [f64; 3],
+    segments: Vec<(f64, [f64; 3], Segment)>,
+}
+impl TrajectoryGenerator {
+    /// Returns the azimuth, elevation and rotator setpoints at time `t`
+    pub fn setpoints(&self, t: f64) -> [f64; 3] {
+        match self
+            .segments
+            .iter()
+            .rev()
+            .find(|(t0, _, _)| *t0 <= t)
+        {
+            Some((t0, position, segment)) => {
+                let delta = segment.delta((t - t0).min(segment.duration()));
+                [
+                    position[0] + delta[0],
+                    position[1] + delta[1],
+                    position[2] + delta[2],
+                ]
+            }
+            None => self.start,
+        }
+    }
+}
+impl IOTags for TrajectoryGenerator {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        vec![jar::MountSP::new()]
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        vec![]
+    }
+}
+impl DOS for TrajectoryGenerator {
+    fn inputs(&mut self, _: Vec<IO<Vec<f64>>>) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        Err("TrajectoryGenerator takes no inputs".into())
+    }
+    fn reset(&mut self) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        self.step = 0;
+        Ok(self)
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        if self.step >= self.n_sample {
+            return None;
+        }
+        let t = self.step as f64 / self.sampling_rate;
+        self.step += 1;
+        Some(vec![jar::MountSP::with(self.setpoints(t).to_vec())])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slew_limits() {
+        let limits = Limits::new(0.02, 0.01, 0.005);
+        let sampling_rate = 100.;
+        let trajectory = Trajectory::new(sampling_rate, [limits; 3])
+            .slew([0.1, -0.001, 0.])
+            .hold(1.0);
+        let duration = trajectory.duration();
+        let mut setpoints = trajectory.build();
+        let mut az = vec![];
+        while let Some(sp) = setpoints.outputs() {
+            let sp = Option::<Vec<f64>>::from(&sp[0]).unwrap();
+            az.push(sp[0]);
+        }
+        assert_eq!(az.len(), (duration * sampling_rate).ceil() as usize + 1);
+        assert!((az.last().unwrap() - 0.1).abs() < 1e-12);
+        assert!((setpoints.setpoints(duration)[1] + 0.001).abs() < 1e-12);
+        let dt = sampling_rate.recip();
+        let v: Vec<f64> = az.windows(2).map(|x| (x[1] - x[0]) / dt).collect();
+        let a: Vec<f64> = v.windows(2).map(|x| (x[1] - x[0]) / dt).collect();
+        assert!(v.iter().all(|v| v.abs() <= limits.velocity * (1. + 1e-6)));
+        assert!(a.iter().all(|a| a.abs() <= limits.acceleration * 1.01));
+        assert!((v.iter().cloned().fold(0f64, f64::max) - limits.velocity).abs() < 1e-6);
+    }
+}
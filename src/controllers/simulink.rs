@@ -4,24 +4,207 @@
 //! This is an interface to build and to run a controller design with Simulink inside Rust
 //! ## Example
 //! A Simulink model named `SimControl` with 1 input `SimIn1` of size 6 and 1 output `SimOut1` of size 3 is imported into Rust with:
-//! ```rust
+//! ```ignore
 //! import_simulink!(SimControl, U : (SimIn1,6), Y : (SimOut1,3))
 //! build_inputs!(In1,6)
 //! build_inputs!(Out1,3)
 //! build_controller!(SimControl, U: (SimIn1 -> (In1,in1)), Y: (SimOut1 -> (Out1,out1)))
 //! ```
+//! ## Parameters
+//! The constant parameters of a Simulink model (the `ConstP_<controller>_T` structure of the generated `<controller>_data.c` file) are imported with an additional `P` section,
+//! e.g. for 2 parameters `SimGain` of size 6 and `SimSS_A` of size 16:
+//! ```ignore
+//! import_simulink!(SimControl, U : (SimIn1,6), Y : (SimOut1,3), P : (SimGain,6,SimSS_A,16))
+//! ```
+//! The parameters are then read and overwritten at runtime with the [`SimulinkParameters`] trait methods.
+//! The parameters that Simulink inlines in the generated step function are not in the structure and cannot be changed without generating the C code again.
+//! ## Internal signals and states
+//! The block signals (`B_<controller>_T`) and the block states (`DW_<controller>_T`) of a Simulink model are imported with:
+//! ```ignore
+//! import_simulink_states!(SimControl, DW : (SimSS_DSTATE,4), B : (SimSum,1))
+//! ```
+//! The signals and states are read with the [`SimulinkStates`] trait methods and named `B.<name>` and `DW.<name>`, e.g. `DW.SimSS_DSTATE`.
+//! Some of them are appended to the controller outputs with [`log_states`](SimulinkStates::log_states):
+//! ```ignore
+//! mnt_ctrl.log_states(jar::MountCtrlStates::new(), &["DW.DiscreteSS_DSTATE", "B.RTout"])?;
+//! ```
+//! Their tags are appended to the controller outputs tags so they can then be logged with [`DataLogging`](crate::DataLogging) like any other output.
+//! ## Build
+//! The C code generated by Simulink is copied into a directory below `src/controllers`, next to the controller module `mod.rs`.
+//! The build script compiles the C code and writes the `import_simulink!` call with the inputs, outputs and parameters names and sizes read from the generated header `<controller>.h`.
+//! The controller module includes it in place of a hand-written `import_simulink!`:
+//! ```ignore
+//! include!(concat!(env!("OUT_DIR"), "/src/controllers/<directory>/import_simulink.rs"));
+//! ```
+//! The build fails if the sizes given to `build_inputs!` and `build_outputs!` do not match the sizes in the header.
+
+use crate::io::{Tags, IO};
+use serde_pickle as pkl;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Serializes the tests that run a Simulink controller
+///
+/// The inputs, outputs, states and parameters of a Simulink controller are global to the C code,
+/// so the tests that run in parallel threads would overwrite each other's values
+#[cfg(all(test, feature = "mount-pdr"))]
+pub(crate) fn test_lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
 
 pub trait Simulink {
     fn initialize(&mut self);
     fn __step__(&self);
     fn terminate(&self);
+    /// Restores the initial states, inputs and outputs of the controller
+    ///
+    /// The parameters are not restored.
+    /// The states, inputs and outputs are global to the C code, so every instance of the controller is reset
+    fn reset(&mut self) {
+        self.terminate();
+        self.initialize();
+    }
+}
+
+/// Runtime access to the constant parameters of a Simulink controller
+///
+/// A parameter is a flat array of `f64` identified by its name in the `ConstP_<controller>_T` structure.
+/// The parameters are global to the C code, so they are shared by all the instances of a controller
+pub trait SimulinkParameters {
+    /// Returns the names of the parameters
+    fn parameters_names(&self) -> Vec<&'static str>;
+    /// Returns the values of the parameter `name`
+    fn parameter(&self, name: &str) -> Option<Vec<f64>>;
+    /// Overwrites the values of the parameter `name`
+    fn set_parameter(&mut self, name: &str, values: &[f64]) -> Result<&mut Self, Box<dyn std::error::Error>>;
+    /// Returns all the parameters
+    fn parameters(&self) -> BTreeMap<String, Vec<f64>> {
+        self.parameters_names()
+            .into_iter()
+            .filter_map(|name| self.parameter(name).map(|p| (name.to_owned(), p)))
+            .collect()
+    }
+    /// Overwrites the parameters in `parameters`, the other parameters are left unchanged
+    fn set_parameters(
+        &mut self,
+        parameters: &BTreeMap<String, Vec<f64>>,
+    ) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        for (name, values) in parameters {
+            self.set_parameter(name, values)?;
+        }
+        Ok(self)
+    }
+    /// Overwrites the parameters with the parameters in a pickle file
+    ///
+    /// The pickle file is a dictionary of the parameters names and values, only the parameters in the dictionary are overwritten
+    fn parameters_from_pickle<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        let f = File::open(path)?;
+        let parameters: BTreeMap<String, Vec<f64>> = pkl::from_reader(BufReader::new(f))?;
+        self.set_parameters(&parameters)
+    }
+    /// Writes all the parameters into a pickle file
+    fn parameters_to_pickle<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        let mut f = File::create(path)?;
+        pkl::to_writer(&mut f, &self.parameters(), true)?;
+        Ok(())
+    }
+}
+
+/// Runtime access to the internal signals and states of a Simulink controller
+///
+/// Only the leading `real_T` fields of the `B_<controller>_T` and `DW_<controller>_T` structures are imported, the fields that follow a field of another type are ignored.
+/// The signals and states are global to the C code, so they are shared by all the instances of a controller
+pub trait SimulinkStates {
+    /// Returns the names of the block signals `B.<name>` and of the block states `DW.<name>`
+    fn states_names(&self) -> Vec<String>;
+    /// Returns the values of the signal or state `name`
+    fn state(&self, name: &str) -> Option<Vec<f64>>;
+    /// Returns the logged signals and states
+    fn states_log(&self) -> &[(Tags, Vec<String>)];
+    /// Returns a mutable reference to the logged signals and states
+    fn states_log_mut(&mut self) -> &mut Vec<(Tags, Vec<String>)>;
+    /// Appends the signals and states `names`, concatenated in the output `tag`, to the controller outputs
+    fn log_states(&mut self, tag: Tags, names: &[&str]) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        if let Some(name) = names.iter().find(|name| self.state(name).is_none()) {
+            return Err(format!("unknown Simulink signal or state: {}", name).into());
+        }
+        self.states_log_mut()
+            .push((tag, names.iter().map(|name| name.to_string()).collect()));
+        Ok(self)
+    }
+    /// Returns the tags of the logged signals and states outputs
+    fn states_tags(&self) -> Vec<Tags> {
+        self.states_log().iter().map(|(tag, _)| tag.clone()).collect()
+    }
+    /// Returns the logged signals and states outputs
+    fn states_outputs(&self) -> Vec<IO<Vec<f64>>> {
+        self.states_log()
+            .iter()
+            .map(|(tag, names)| {
+                let data: Vec<f64> = names.iter().filter_map(|name| self.state(name)).flatten().collect();
+                IO::from((tag, data))
+            })
+            .collect()
+    }
 }
 
 /// Import Simulink C definitions
 ///
 /// An Simulink C import is written:  `(Simulink controller name, U : (<Simulink input name,size>,<...>,...), Y : (<Simulink output name,size>,<...>,...))`
+/// or `(Simulink controller name, U : (...), Y : (...), P : (<Simulink parameter name,size>,<...>,...))` to import the constant parameters as well
 #[macro_export]
 macro_rules! import_simulink {
+    ($controller:ident, U : ($($sim_u:ident, $size_u:expr),+), Y : ($($sim_y:ident, $size_y:expr),+), P : ($($sim_p:ident, $size_p:expr),+)) => {
+        $crate::import_simulink!($controller, U : ($($sim_u, $size_u),+), Y : ($($sim_y, $size_y),+));
+        paste::paste!{
+            /// Simulink constant parameters (P)
+            #[repr(C)]
+            #[allow(non_snake_case)]
+            #[derive(Debug)]
+            struct [<ConstP_ $controller _T>] {
+            $($sim_p: [f64;$size_p],)+
+        }}
+        paste::paste!{
+        extern "C" {
+            static mut [<$controller _ConstP>]: [<ConstP_ $controller _T>];
+        }}
+        paste::paste!{
+        impl<'a> $crate::controllers::SimulinkParameters for Controller<'a> {
+            fn parameters_names(&self) -> Vec<&'static str> {
+                vec![$(stringify!($sim_p)),+]
+            }
+            fn parameter(&self, name: &str) -> Option<Vec<f64>> {
+                unsafe {
+                    match name {
+                        $(stringify!($sim_p) => Some((*std::ptr::addr_of!([<$controller _ConstP>].$sim_p)).to_vec()),)+
+                        _ => None,
+                    }
+                }
+            }
+            fn set_parameter(&mut self, name: &str, values: &[f64]) -> Result<&mut Self, Box<dyn std::error::Error>> {
+                let parameter: &mut [f64] = unsafe {
+                    match name {
+                        $(stringify!($sim_p) => &mut *std::ptr::addr_of_mut!([<$controller _ConstP>].$sim_p),)+
+                        _ => return Err(format!("unknown {} parameter: {}", stringify!($controller), name).into()),
+                    }
+                };
+                if parameter.len() != values.len() {
+                    return Err(format!(
+                        "{} parameter {} has {} values, {} given",
+                        stringify!($controller), name, parameter.len(), values.len()
+                    ).into());
+                }
+                parameter.copy_from_slice(values);
+                Ok(self)
+            }
+        }}
+    };
     ($controller:ident, U : ($($sim_u:ident, $size_u:expr),+), Y : ($($sim_y:ident, $size_y:expr),+)) => {
         paste::paste!{
             /// Simulink external input (U)
@@ -51,6 +234,48 @@ macro_rules! import_simulink {
     };
 }
 
+/// Import Simulink internal signals and states
+///
+/// An import is written: `(Simulink controller name, DW : (<Simulink state name,size>,<...>,...), B : (<Simulink signal name,size>,<...>,...))`,
+/// either section is optional
+#[macro_export]
+macro_rules! import_simulink_states {
+    ($controller:ident $(, $section:ident : ($($name:ident, $size:expr),+))*) => {
+        $(
+        paste::paste!{
+            /// Simulink block states (DW) or signals (B)
+            #[repr(C)]
+            #[allow(non_snake_case)]
+            #[derive(Debug)]
+            struct [<$section _ $controller _T>] {
+            $($name: [f64;$size],)+
+        }}
+        paste::paste!{
+        extern "C" {
+            static mut [<$controller _ $section>]: [<$section _ $controller _T>];
+        }}
+        )*
+        paste::paste!{
+        impl<'a> $crate::controllers::SimulinkStates for Controller<'a> {
+            fn states_names(&self) -> Vec<String> {
+                vec![$($(concat!(stringify!($section), ".", stringify!($name)).to_owned()),+),*]
+            }
+            fn state(&self, name: &str) -> Option<Vec<f64>> {
+                match name {
+                    $($(concat!(stringify!($section), ".", stringify!($name)) => Some(unsafe { (*std::ptr::addr_of!([<$controller _ $section>].$name)).to_vec() }),)+)*
+                    _ => None,
+                }
+            }
+            fn states_log(&self) -> &[($crate::io::Tags, Vec<String>)] {
+                &self.states
+            }
+            fn states_log_mut(&mut self) -> &mut Vec<($crate::io::Tags, Vec<String>)> {
+                &mut self.states
+            }
+        }}
+    };
+}
+
 /// Build the controller inputs
 ///
 /// An input definition is: `(<enum name,size>,<...>,...)` or `(<enum name,size,offset>,<...>,...)` with
@@ -168,6 +393,7 @@ macro_rules! build_controller {
         pub struct Controller<'a> {
             $(pub $var_u: U<'a>,)+
             $(pub $var_y: Y<'a>,)+
+            states: Vec<($crate::io::Tags, Vec<String>)>,
         }
         paste::paste!{
         impl<'a> Controller<'a> {
@@ -175,13 +401,19 @@ macro_rules! build_controller {
             pub fn new() -> Self {
                 let mut this = unsafe {
                     Self {
-                        $($var_u: U::$enum_u(&mut [<$controller _U>].$sim_u),)+
-                        $($var_y: Y::$enum_y(&mut [<$controller _Y>].$sim_y),)+
+                        $($var_u: U::$enum_u(&mut *std::ptr::addr_of_mut!([<$controller _U>].$sim_u)),)+
+                        $($var_y: Y::$enum_y(&mut *std::ptr::addr_of_mut!([<$controller _Y>].$sim_y)),)+
+                        states: vec![],
                     }
                 };
                 this.initialize();
                 this
             }
+        }
+        impl<'a> Default for Controller<'a> {
+            fn default() -> Self {
+                Self::new()
+            }
         }}
         use $crate::controllers::Simulink;
         paste::paste! {
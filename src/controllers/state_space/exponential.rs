@@ -126,6 +126,11 @@ impl Exponential {
             x: (0f64, 0f64),
         }
     }
+    /// Sets the state and the output to 0
+    pub fn reset(&mut self) {
+        self.x = (0f64, 0f64);
+        self.y.iter_mut().for_each(|y| *y = 0f64);
+    }
     /// Returns the state space model output
     pub fn solve(&mut self, u: &[f64]) -> &[f64] {
         let (x0, x1) = self.x;
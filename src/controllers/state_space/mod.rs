@@ -37,7 +37,6 @@
 //! }
 //! ```
 
-use crate::fem;
 use crate::{
     io::{IOError, Tags},
     DOSError, IOTags, DOS, IO,
@@ -105,7 +104,7 @@ impl DiscreteStateSpace {
     /// Overwrites some eigen frequencies in Hz
     ///
     /// Example
-    /// ```rust
+    /// ```ignore
     /// // Setting the 1st 3 eigen values to 0
     /// fem_ss.eigen_frequencies(vec![(0,0.),(1,0.),(2,0.)])
     /// ```
@@ -138,10 +137,9 @@ impl DiscreteStateSpace {
     /// Sets the model inputs from a vector of [IO]
     pub fn inputs(self, mut v_u: Vec<Tags>) -> Self {
         let mut u = self.u;
-        if u.is_none() {
-            u = Some(v_u);
-        } else {
-            u.as_mut().unwrap().append(&mut v_u);
+        match u.as_mut() {
+            Some(u) => u.append(&mut v_u),
+            None => u = Some(v_u),
         }
         Self { u, ..self }
     }
@@ -152,10 +150,9 @@ impl DiscreteStateSpace {
     /// Sets the model outputs from a vector of [IO]
     pub fn outputs(self, mut v_y: Vec<Tags>) -> Self {
         let mut y = self.y;
-        if y.is_none() {
-            y = Some(v_y);
-        } else {
-            y.as_mut().unwrap().append(&mut v_y);
+        match y.as_mut() {
+            Some(y) => y.append(&mut v_y),
+            None => y = Some(v_y),
         }
         Self { y, ..self }
     }
@@ -263,24 +260,15 @@ impl DiscreteStateSpace {
             ))),
             |x| Ok(1f64 / x),
         )?;
-        let mut fem = self.fem.map_or(
-            Err(DOSError::Component(StateSpaceError::MissingArguments(
-                "FEM".to_owned(),
-            ))),
-            Ok,
-        )?;
-        let dos_inputs = self.u.map_or(
-            Err(DOSError::Component(StateSpaceError::MissingArguments(
-                "inputs".to_owned(),
-            ))),
-            Ok,
-        )?;
-        let dos_outputs = self.y.map_or(
-            Err(DOSError::Component(StateSpaceError::MissingArguments(
-                "outputs".to_owned(),
-            ))),
-            Ok,
-        )?;
+        let mut fem = self.fem.ok_or_else(|| {
+            DOSError::Component(StateSpaceError::MissingArguments("FEM".to_owned()))
+        })?;
+        let dos_inputs = self.u.ok_or_else(|| {
+            DOSError::Component(StateSpaceError::MissingArguments("inputs".to_owned()))
+        })?;
+        let dos_outputs = self.y.ok_or_else(|| {
+            DOSError::Component(StateSpaceError::MissingArguments("outputs".to_owned()))
+        })?;
         Self::select_fem_io(&mut fem, &dos_inputs, &dos_outputs);
         let forces_2_modes = na::DMatrix::from_row_slice(
             fem.n_modes(),
@@ -416,13 +404,19 @@ impl Iterator for DiscreteModalSolver<Exponential> {
 }
 
 impl DOS for DiscreteModalSolver<Exponential> {
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        self.state_space.iter_mut().for_each(|m| m.reset());
+        self.u.iter_mut().for_each(|u| *u = 0f64);
+        self.y.iter_mut().for_each(|y| *y = 0f64);
+        Ok(self)
+    }
     fn inputs(
         &mut self,
         data: Vec<IO<Vec<f64>>>,
     ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
         self.u = data
             .into_iter()
-            .map(|x| std::result::Result::<Vec<f64>, DOSError<IOError>>::from(x))
+            .map(std::result::Result::<Vec<f64>, DOSError<IOError>>::from)
             .collect::<std::result::Result<Vec<Vec<f64>>, DOSError<IOError>>>()?
             .into_iter()
             .flatten()
@@ -22,6 +22,18 @@ impl<T: fmt::Debug> From<serde_pickle::Error> for DOSError<T> {
     }
 }
 
+impl<T: fmt::Debug> From<csv::Error> for DOSError<T> {
+    fn from(e: csv::Error) -> DOSError<T> {
+        DOSError::IO(Box::new(e))
+    }
+}
+
+impl<T: fmt::Debug> From<zip::result::ZipError> for DOSError<T> {
+    fn from(e: zip::result::ZipError) -> DOSError<T> {
+        DOSError::IO(Box::new(e))
+    }
+}
+
 impl<T: fmt::Debug> fmt::Display for DOSError<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         use DOSError::*;
@@ -0,0 +1,90 @@
+//! Data file formats shared by the components
+//!
+//! NumPy arrays are read with [`read_npy`] from the `.npy` files of a NumPy archive, e.g. the wind loads ([`WindLoads::from_npz`](crate::wind_loads::WindLoads::from_npz))
+//! or the state space controllers matrices ([`StateSpace::from_npz`](crate::controllers::linear::StateSpace::from_npz)).
+
+/// Parses a NumPy array file returning the array shape and the array values in row major order
+pub(crate) fn read_npy(buffer: &[u8]) -> std::result::Result<(Vec<usize>, Vec<f64>), String> {
+    if buffer.len() < 10 || &buffer[..6] != b"\x93NUMPY" {
+        return Err("not a NumPy array".to_owned());
+    }
+    let (header_len, offset) = match buffer[6] {
+        1 => (u16::from_le_bytes([buffer[8], buffer[9]]) as usize, 10),
+        _ if buffer.len() >= 12 => (
+            u32::from_le_bytes([buffer[8], buffer[9], buffer[10], buffer[11]]) as usize,
+            12,
+        ),
+        _ => return Err("truncated NumPy header".to_owned()),
+    };
+    let header = buffer
+        .get(offset..offset + header_len)
+        .and_then(|x| std::str::from_utf8(x).ok())
+        .ok_or("invalid NumPy header")?;
+    let field = |key: &str| -> Option<&str> {
+        header
+            .split(&format!("'{}':", key)[..])
+            .nth(1)
+            .map(|x| x.trim_start())
+    };
+    let descr = field("descr")
+        .and_then(|x| x.split('\'').nth(1))
+        .ok_or("missing NumPy descr")?;
+    let fortran_order = matches!(field("fortran_order"), Some(x) if x.starts_with("True"));
+    let shape: Vec<usize> = field("shape")
+        .and_then(|x| x.strip_prefix('('))
+        .and_then(|x| x.split(')').next())
+        .ok_or("missing NumPy shape")?
+        .split(',')
+        .filter(|x| !x.trim().is_empty())
+        .map(|x| x.trim().parse::<usize>().map_err(|e| e.to_string()))
+        .collect::<std::result::Result<Vec<usize>, String>>()?;
+    let data = &buffer[offset + header_len..];
+    let values: Vec<f64> = match descr {
+        "<f8" => data
+            .chunks_exact(8)
+            .map(|b| {
+                let mut x = [0u8; 8];
+                x.copy_from_slice(b);
+                f64::from_le_bytes(x)
+            })
+            .collect(),
+        "<f4" => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64)
+            .collect(),
+        _ => return Err(format!("unsupported NumPy dtype: {}", descr)),
+    };
+    let n: usize = shape.iter().product();
+    if values.len() < n {
+        return Err("truncated NumPy data".to_owned());
+    }
+    let values = if fortran_order && shape.len() == 2 {
+        let (n_row, n_col) = (shape[0], shape[1]);
+        (0..n_row)
+            .flat_map(|i| (0..n_col).map(move |j| (i, j)))
+            .map(|(i, j)| values[i + j * n_row])
+            .collect()
+    } else {
+        values[..n].to_vec()
+    };
+    Ok((shape, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npy_fortran_order() {
+        let header = "{'descr': '<f8', 'fortran_order': True, 'shape': (3, 2), }";
+        let mut buffer = b"\x93NUMPY\x01\x00".to_vec();
+        buffer.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        buffer.extend_from_slice(header.as_bytes());
+        for x in &[1f64, 3., 5., 2., 4., 6.] {
+            buffer.extend_from_slice(&x.to_le_bytes());
+        }
+        let (shape, values) = read_npy(&buffer).unwrap();
+        assert_eq!(shape, vec![3, 2]);
+        assert_eq!(values, vec![1., 2., 3., 4., 5., 6.]);
+    }
+}
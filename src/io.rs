@@ -10,6 +10,7 @@ use std::ops::{AddAssign, Index, IndexMut, SubAssign};
 #[derive(Clone, Debug)]
 pub enum IOError {
     Missing(String),
+    Unknown(String),
 }
 
 macro_rules! build_io {
@@ -27,6 +28,30 @@ macro_rules! build_io {
                 }
             }
         }
+        impl<T> IO<T> {
+            /// Applies `f` to the `IO` `data`
+            pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> IO<U> {
+                match self {
+                    $(IO::$variant{ data: values} => IO::$variant{ data: values.map(f)}),+
+                }
+            }
+            /// Returns a mutable reference to the `IO` `data`
+            pub fn as_mut(&mut self) -> Option<&mut T> {
+                match self {
+                    $(IO::$variant{ data: values} => values.as_mut()),+
+                }
+            }
+        }
+        impl<T> std::str::FromStr for IO<T> {
+            type Err = IOError;
+            /// Creates a `IO` from the name of the variant with `data` set to `None`
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $(stringify!($variant) => Ok(IO::$variant{ data: None}),)+
+                    _ => Err(IOError::Unknown(s.to_owned())),
+                }
+            }
+        }
         impl<T,U> PartialEq<IO<T>> for IO<U> {
             fn eq(&self, other: &IO<T>) -> bool {
                 match (self,other) {
@@ -66,7 +91,7 @@ macro_rules! build_io {
         impl<T> From<(&IO<()>,Option<T>)> for IO<T> {
             fn from((io,data): (&IO<()>,Option<T>)) -> Self {
                 match io {
-                    $(IO::$variant{ .. } => IO::$variant{ data: data}),+
+                    $(IO::$variant{ .. } => IO::$variant{ data }),+
                 }
             }
         }
@@ -138,6 +163,7 @@ macro_rules! build_io {
             $(pub struct $variant {}
               impl $variant {
                   /// Creates a new `IO` type variant with `data` set to `None`
+                  #[allow(clippy::new_ret_no_self)]
                   pub fn new<T>() -> IO<T> {
                       IO::$variant{ data: None}
                   }
@@ -324,11 +350,21 @@ build_io!(
     M2ReferenceBody5AxialD,
     M2ReferenceBody6AxialD,
     M2ReferenceBody7AxialD,
+    // Mount control
     MountCmd,
+    MountSP,
     // M1 control
     M1HPCmd,
     M1HPLC,
-    M1CGFM
+    M1CGFM,
+    // M2 control
+    M2PosCmd,
+    M2RBMCmd,
+    M2ASMCmd,
+    // Simulink controllers internal signals and states
+    MountCtrlStates,
+    MountDrivesStates,
+    M1CGStates
 );
 
 io_match_fem!(
@@ -349,7 +385,9 @@ io_match_fem!(
             OSSHarpointDeltaF,
             OSSAzDriveTorque,
             OSSElDriveTorque,
-            OSSRotDriveTorque
+            OSSRotDriveTorque,
+            MCM2SmHexF,
+            MCM2CP6F
         ),
     outputs:
         (
@@ -362,7 +400,15 @@ io_match_fem!(
             OSSAzEncoderAngle,
             OSSElEncoderAngle,
             OSSRotEncoderAngle,
-            MCM2RB6D
+            MCM2RB6D,
+            MCM2SmHexD,
+            M2ReferenceBody1AxialD,
+            M2ReferenceBody2AxialD,
+            M2ReferenceBody3AxialD,
+            M2ReferenceBody4AxialD,
+            M2ReferenceBody5AxialD,
+            M2ReferenceBody6AxialD,
+            M2ReferenceBody7AxialD
         )
 );
 io_match_wind_loads!(
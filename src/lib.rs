@@ -10,21 +10,34 @@
 //! An end-to-end simulation is divided into components, each represented by a structure:
 //!  - [`DiscreteModalsolver`](crate::controllers::state_space::DiscreteStateSpace) for the finite element model of the telescope ([example](crate::controllers::state_space)),
 //!  - [`WindLoading`] for the wind loads,
-//!  - `Controller` for each subsystem controller.
+//!  - [`SignalGenerator`](crate::signals::SignalGenerator) for the test signals,
+//!  - `Controller` for each subsystem controller,
+//!  - `MountSystem` in `controllers::mount::system` for either version of the mount controller and drives, selected at runtime,
+//!  - [`TellTale`](crate::telltale::TellTale) for the data logger, built with [`DataLogging`],
+//!  - [`Monitor`](crate::monitor::Monitor) for the actuators saturation and rate limits, built with [`Monitoring`](crate::monitor::Monitoring).
 //!
 //! Each component structure contains a [`Vec`] of either inputs, outputs or both that corresponds to some variant of the [`IO`] enum type.
 //! Each component structure must implement the [`Iterator`] and the [`DOS`] traits.
 //! The [`next`](core::iter::Iterator::next) method of the [`Iterator`] trait is used to update the state of the component at each time step.
 //! The [`inputs`](crate::DOS::inputs) method of the [`DOS`] trait passes inputs data to the components whereas the [`outputs`](crate::DOS::outputs) method returns the component outputs.
+//!
+//! The Simulink controllers are compiled according to the cargo features, all enabled by default:
+//!  - `mount-baseline` for `controllers::mount::controller` and `controllers::mount::drives`,
+//!  - `mount-pdr` for `controllers::mount::pdr`,
+//!  - `m1` for the M1 hardpoints load cells, local and center of gravity controllers in [`controllers::m1`].
+//!
+//! e.g. `cargo build --no-default-features --features mount-pdr` for the mount PDR controllers only.
 
 pub mod controllers;
 pub mod io;
+pub mod monitor;
+pub mod signals;
 pub mod telltale;
 pub mod wind_loads;
 pub mod error;
+mod formats;
 
 use error::DOSError;
-use fem;
 use io::IO;
 #[doc(inline)]
 pub use telltale::DataLogging;
@@ -44,6 +57,12 @@ pub trait DOS {
     fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>>;
     /// Passes a vector of input data to a model component
     fn inputs(&mut self, data: Vec<IO<Vec<f64>>>) -> Result<&mut Self, Box<dyn std::error::Error>>;
+    /// Restores the initial state of a model component
+    ///
+    /// The component can then run another simulation without being built again
+    fn reset(&mut self) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        Err("the component cannot be reset".into())
+    }
     /// Updates the state of a model component for one time step
     fn step(&mut self) -> Result<&mut Self, DOSError<()>>
     where
@@ -54,6 +73,7 @@ pub trait DOS {
     /// Combines `inputs`, `step` and `outputs` in a single method
     ///
     /// This is equivalent to `.inputs(...)?.step()?.outputs()?`
+    #[allow(clippy::type_complexity)]
     fn in_step_out(
         &mut self,
         data: Vec<IO<Vec<f64>>>,
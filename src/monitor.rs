@@ -0,0 +1,496 @@
+//! This module is used to monitor the saturation and the rate limits of the actuators
+//!
+//! The limits monitor [`Monitor`] is built with [`Monitoring`] and, as any other component, implements the [`DOS`], [`IOTags`] and [`Iterator`] traits.
+//! The data passed to [`inputs`](crate::DOS::inputs) are checked against the [`Limits`] of their channels at each [`step`](crate::DOS::step)
+//! and are returned by [`outputs`](crate::DOS::outputs), optionally clamped to the limits.
+//! The data without limits are passed through unchanged so the monitor can be inserted after any component.
+//! The rates of change are checked on the input data while the clamped data are rate limited with respect to the previous clamped data.
+//!
+//! For each channel, the monitor counts the samples outside the limits and records the time of the first and last violations
+//! and the time of the onset of each violation as an [`Event`].
+//! The [`Summary`] of the violations is given at the end of the run by [`Monitor::summary`].
+//!
+//! # Example
+//! Monitoring the mount drives torques, clamping the azimuth torques to 20kN.m and 1MN.m/s
+//! ```no_run
+//! use dos::{
+//!     io::jar,
+//!     monitor::{Limits, Monitoring},
+//!     signals::{Signal, Signals},
+//!     DOS,
+//! };
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut torques = Signals::new(1e3)
+//!         .n_sample(10_000)
+//!         .output(
+//!             jar::OSSAzDriveTorque::new(),
+//!             12,
+//!             Signal::WhiteNoise {
+//!                 std: 1e4,
+//!                 bandwidth: 50.0,
+//!             },
+//!         )
+//!         .output(jar::OSSElDriveTorque::new(), 4, Signal::Constant(1e4))
+//!         .build()?;
+//!     let mut monitor = Monitoring::new()
+//!         .sampling_rate(1e3)
+//!         .limits(
+//!             jar::OSSAzDriveTorque::new(),
+//!             Limits::new().absolute(2e4).rate(1e6),
+//!         )
+//!         .limits(jar::OSSElDriveTorque::new(), Limits::new().absolute(3e4))
+//!         .clamp(jar::OSSAzDriveTorque::new())
+//!         .build();
+//!     while let Some(u) = torques.outputs() {
+//!         let mount_torques = monitor.in_step_out(u)?;
+//!         // ...
+//!     }
+//!     println!("{}", monitor.summary());
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    io::{IOError, Tags, IO},
+    DOSError, IOTags, DOS,
+};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum MonitorError {
+    /// Number of channel limits different from the number of channels
+    Size(Tags, usize, usize),
+}
+
+/// Limits of a channel
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    /// Lower and upper bounds
+    pub range: Option<(f64, f64)>,
+    /// Maximum magnitude of the rate of change per second
+    pub rate: Option<f64>,
+}
+impl Limits {
+    /// Creates limits without bounds
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Sets the lower and upper bounds
+    pub fn range(self, min: f64, max: f64) -> Self {
+        Self {
+            range: Some((min, max)),
+            ..self
+        }
+    }
+    /// Sets the bounds to `[-max,max]`
+    pub fn absolute(self, max: f64) -> Self {
+        self.range(-max, max)
+    }
+    /// Sets the maximum magnitude of the rate of change per second
+    pub fn rate(self, rate: f64) -> Self {
+        Self {
+            rate: Some(rate),
+            ..self
+        }
+    }
+}
+
+/// Limits violations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    /// Value below the lower bound
+    Below,
+    /// Value above the upper bound
+    Above,
+    /// Rate of change above the rate limit
+    Rate,
+}
+impl Violation {
+    fn index(&self) -> usize {
+        match self {
+            Violation::Below => 0,
+            Violation::Above => 1,
+            Violation::Rate => 2,
+        }
+    }
+}
+
+/// Onset of a limit violation
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// Time in seconds
+    pub time: f64,
+    pub key: Tags,
+    /// Channel index
+    pub channel: usize,
+    pub violation: Violation,
+    /// Value or rate of change that violates the limit
+    pub value: f64,
+}
+
+/// Limits violations statistics of a channel
+#[derive(Debug, Clone, Default)]
+pub struct ChannelStats {
+    /// Number of samples below the lower bound, above the upper bound and above the rate limit
+    pub counts: [usize; 3],
+    /// Time of the first violation
+    pub first: Option<f64>,
+    /// Time of the last violation
+    pub last: Option<f64>,
+    /// Largest magnitude of the values
+    pub peak: f64,
+    /// Largest magnitude of the rates of change
+    pub peak_rate: f64,
+    active: [bool; 3],
+}
+impl ChannelStats {
+    /// Returns the number of violations of the given kind
+    pub fn count(&self, violation: Violation) -> usize {
+        self.counts[violation.index()]
+    }
+    /// Returns the total number of violations
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+}
+
+struct Monitored {
+    key: Tags,
+    limits: Vec<Limits>,
+    clamp: bool,
+    // Previous input values, used to check the rate limits
+    previous: Option<Vec<f64>>,
+    // Previous output values, used to clamp the rate
+    clamped: Option<Vec<f64>>,
+    stats: Vec<ChannelStats>,
+}
+impl Monitored {
+    // Checks and optionally clamps the values, returning the violations onsets
+    fn check(&mut self, values: &mut [f64], time: f64, tau: f64) -> Vec<Event> {
+        let mut events = vec![];
+        if self.stats.len() != values.len() {
+            self.stats = vec![Default::default(); values.len()];
+        }
+        let mut previous = Vec::with_capacity(values.len());
+        for (k, value) in values.iter_mut().enumerate() {
+            let limits = if self.limits.len() == 1 {
+                self.limits[0]
+            } else {
+                self.limits[k]
+            };
+            let stats = &mut self.stats[k];
+            stats.peak = stats.peak.max(value.abs());
+            let raw = *value;
+            let mut violations = vec![];
+            if let (Some(max_rate), Some(previous)) = (limits.rate, &self.previous) {
+                let rate = (raw - previous[k]) / tau;
+                stats.peak_rate = stats.peak_rate.max(rate.abs());
+                if rate.abs() > max_rate {
+                    violations.push((Violation::Rate, rate));
+                }
+            }
+            if let (true, Some(max_rate), Some(clamped)) = (self.clamp, limits.rate, &self.clamped)
+            {
+                let delta = max_rate * tau;
+                *value = value.max(clamped[k] - delta).min(clamped[k] + delta);
+            }
+            if let Some((min, max)) = limits.range {
+                if raw < min {
+                    violations.push((Violation::Below, raw));
+                } else if raw > max {
+                    violations.push((Violation::Above, raw));
+                }
+                if self.clamp {
+                    *value = value.max(min).min(max);
+                }
+            }
+            let mut active = [false; 3];
+            for (violation, value) in violations {
+                let i = violation.index();
+                active[i] = true;
+                stats.counts[i] += 1;
+                stats.first.get_or_insert(time);
+                stats.last = Some(time);
+                if !stats.active[i] {
+                    events.push(Event {
+                        time,
+                        key: self.key.clone(),
+                        channel: k,
+                        violation,
+                        value,
+                    });
+                }
+            }
+            stats.active = active;
+            previous.push(raw);
+        }
+        self.previous = Some(previous);
+        self.clamped = Some(values.to_vec());
+        events
+    }
+}
+
+/// Actuators limits monitor
+pub struct Monitor {
+    pub sampling_rate: f64,
+    /// Onsets of the limits violations
+    pub events: Vec<Event>,
+    monitors: Vec<Monitored>,
+    data: Vec<IO<Vec<f64>>>,
+    index: usize,
+}
+impl Monitor {
+    /// Returns the current time index
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// Returns the violations statistics of each channel of the `IO` corresponding to `key`
+    pub fn stats(&self, key: Tags) -> Option<&[ChannelStats]> {
+        self.monitors
+            .iter()
+            .find(|m| m.key == key)
+            .map(|m| m.stats.as_slice())
+    }
+    /// Returns the summary of the limits violations
+    pub fn summary(&self) -> Summary {
+        Summary {
+            duration: self.index as f64 / self.sampling_rate,
+            keys: self
+                .monitors
+                .iter()
+                .map(|m| (m.key.clone(), m.stats.clone()))
+                .collect(),
+        }
+    }
+}
+impl Iterator for Monitor {
+    type Item = ();
+    fn next(&mut self) -> Option<Self::Item> {
+        let tau = self.sampling_rate.recip();
+        let time = self.index as f64 * tau;
+        for io in self.data.iter_mut() {
+            if let Some(monitor) = self.monitors.iter_mut().find(|m| m.key == *io) {
+                if let Some(values) = io.as_mut() {
+                    let events = monitor.check(values, time, tau);
+                    for e in &events {
+                        log::warn!(
+                            "{:.3}s: {} #{} {:?} limit violation ({})",
+                            e.time,
+                            name(&e.key),
+                            e.channel,
+                            e.violation,
+                            e.value
+                        );
+                    }
+                    self.events.extend(events);
+                }
+            }
+        }
+        self.index += 1;
+        Some(())
+    }
+}
+impl IOTags for Monitor {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        self.inputs_tags()
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        self.monitors.iter().map(|m| m.key.clone()).collect()
+    }
+}
+impl DOS for Monitor {
+    fn inputs(
+        &mut self,
+        data: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        for io in &data {
+            if let Some(monitor) = self.monitors.iter().find(|m| m.key == *io) {
+                let n = std::result::Result::<Vec<f64>, DOSError<IOError>>::from(io.clone())?.len();
+                if monitor.limits.len() != 1 && monitor.limits.len() != n {
+                    return Err(DOSError::Component(MonitorError::Size(
+                        monitor.key.clone(),
+                        monitor.limits.len(),
+                        n,
+                    ))
+                    .into());
+                }
+            }
+        }
+        self.data = data;
+        Ok(self)
+    }
+    /// Returns the inputs data, the data of the clamped keys are clamped to the limits
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        Some(self.data.clone())
+    }
+    /// Clears the violations statistics and events
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        for monitor in self.monitors.iter_mut() {
+            monitor.previous = None;
+            monitor.clamped = None;
+            monitor.stats.clear();
+        }
+        self.events.clear();
+        self.data.clear();
+        self.index = 0;
+        Ok(self)
+    }
+}
+
+/// Summary of the limits violations
+pub struct Summary {
+    /// Duration of the run in seconds
+    pub duration: f64,
+    /// Violations statistics of each channel of each monitored `IO`
+    pub keys: Vec<(Tags, Vec<ChannelStats>)>,
+}
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Limits violations over {:.3}s:", self.duration)?;
+        for (key, stats) in &self.keys {
+            let n = stats.iter().filter(|s| s.total() > 0).count();
+            writeln!(f, " - {}: {}/{} channels", name(key), n, stats.len())?;
+            for (k, s) in stats.iter().enumerate().filter(|(_, s)| s.total() > 0) {
+                writeln!(
+                    f,
+                    "   #{:<3}: {} below, {} above, {} rate from {:.3}s to {:.3}s (peak: {:.3e}, peak rate: {:.3e})",
+                    k,
+                    s.counts[0],
+                    s.counts[1],
+                    s.counts[2],
+                    s.first.unwrap_or_default(),
+                    s.last.unwrap_or_default(),
+                    s.peak,
+                    s.peak_rate
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The IO variant name
+fn name(key: &Tags) -> String {
+    format!("{:?}", key)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Limits monitor builder
+pub struct Monitoring {
+    pub sampling_rate: f64,
+    monitors: Vec<Monitored>,
+}
+impl Default for Monitoring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Monitoring {
+    pub fn new() -> Self {
+        Self {
+            sampling_rate: 1f64,
+            monitors: vec![],
+        }
+    }
+    pub fn sampling_rate(self, sampling_rate: f64) -> Self {
+        Self {
+            sampling_rate,
+            ..self
+        }
+    }
+    /// Sets the same limits to all the channels of `key`
+    pub fn limits(self, key: Tags, limits: Limits) -> Self {
+        self.channel_limits(key, vec![limits])
+    }
+    /// Sets the limits of each channel of `key`
+    pub fn channel_limits(self, key: Tags, limits: Vec<Limits>) -> Self {
+        let mut monitors = self.monitors;
+        match monitors.iter_mut().find(|m| m.key == key) {
+            Some(monitor) => monitor.limits = limits,
+            None => monitors.push(Monitored {
+                key,
+                limits,
+                clamp: false,
+                previous: None,
+                clamped: None,
+                stats: vec![],
+            }),
+        }
+        Self { monitors, ..self }
+    }
+    /// Clamps the data of `key` to its limits
+    pub fn clamp(self, key: Tags) -> Self {
+        let mut monitors = self.monitors;
+        if let Some(monitor) = monitors.iter_mut().find(|m| m.key == key) {
+            monitor.clamp = true;
+        }
+        Self { monitors, ..self }
+    }
+    pub fn build(self) -> Monitor {
+        Monitor {
+            sampling_rate: self.sampling_rate,
+            events: vec![],
+            monitors: self.monitors,
+            data: vec![],
+            index: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::jar;
+
+    #[test]
+    fn monitor_limits() {
+        let mut monitor = Monitoring::new()
+            .limits(
+                jar::OSSElDriveTorque::new(),
+                Limits::new().absolute(1.).rate(0.5),
+            )
+            .clamp(jar::OSSElDriveTorque::new())
+            .channel_limits(
+                jar::OSSRotDriveTorque::new(),
+                vec![Limits::new().range(0., 1.), Limits::new()],
+            )
+            .build();
+        let mut y = vec![];
+        for x in [0., 0.5, 2., 2., -2.].iter() {
+            let out = monitor
+                .in_step_out(vec![
+                    jar::OSSElDriveTorque::with(vec![*x]),
+                    jar::OSSRotDriveTorque::with(vec![*x, *x]),
+                    jar::MountCmd::with(vec![*x]),
+                ])
+                .unwrap()
+                .unwrap();
+            y.push(Option::<Vec<f64>>::from(&out[0]).unwrap()[0]);
+            assert_eq!(Option::<Vec<f64>>::from(&out[1]).unwrap()[1], *x);
+            assert_eq!(Option::<Vec<f64>>::from(&out[2]).unwrap()[0], *x);
+        }
+        assert_eq!(y, vec![0., 0.5, 1., 1., 0.5]);
+        let el = &monitor.stats(jar::OSSElDriveTorque::new()).unwrap()[0];
+        assert_eq!(el.counts, [1, 2, 2]);
+        assert_eq!((el.first, el.last), (Some(2.), Some(4.)));
+        let rot = monitor.stats(jar::OSSRotDriveTorque::new()).unwrap();
+        assert_eq!((rot[0].counts, rot[1].total()), ([1, 2, 0], 0));
+        assert_eq!(monitor.events.len(), 6);
+        assert_eq!(
+            monitor.summary().to_string(),
+            "Limits violations over 5.000s:
+ - OSSElDriveTorque: 1/1 channels
+   #0  : 1 below, 2 above, 2 rate from 2.000s to 4.000s (peak: 2.000e0, peak rate: 4.000e0)
+ - OSSRotDriveTorque: 1/2 channels
+   #0  : 1 below, 2 above, 0 rate from 2.000s to 4.000s (peak: 2.000e0, peak rate: 0.000e0)
+"
+        );
+        assert!(monitor
+            .inputs(vec![jar::OSSRotDriveTorque::with(vec![0.; 3])])
+            .is_err());
+    }
+}
@@ -0,0 +1,468 @@
+//! This module is used to create test signal sources for the identification of the telescope subsystems
+//!
+//! A [`SignalGenerator`] source is built with [`Signals`] and outputs a [`Signal`] on each channel of any `IO`.
+//! The available signals are steps, sines, swept sines (chirps), band-limited white noise and pseudo-random binary sequences (PRBS).
+//!
+//! # Example
+//! A 10s logarithmic sweep from 1Hz to 100Hz on the first azimuth drive and a PRBS on the elevation drives
+//! ```no_run
+//! use dos::{io::jar, signals::{Signal, Signals}, DOS};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut signals = Signals::new(1e3)
+//!         .n_sample(10_000)
+//!         .output_channel(
+//!             jar::OSSAzDriveTorque::new(),
+//!             12,
+//!             0,
+//!             Signal::Chirp {
+//!                 amplitude: 1e3,
+//!                 f_start: 1.0,
+//!                 f_end: 100.0,
+//!                 duration: 10.0,
+//!                 logarithmic: true,
+//!             },
+//!         )
+//!         .output(
+//!             jar::OSSElDriveTorque::new(),
+//!             4,
+//!             Signal::Prbs {
+//!                 amplitude: 1e2,
+//!                 order: 11,
+//!                 period: 2,
+//!             },
+//!         )
+//!         .build()?;
+//!     while let Some(torques) = signals.outputs() {
+//!         // ...
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{io::Tags, wind_loads::filters, DOSError, IOTags, DOS, IO};
+use rand::{rngs::StdRng, SeedableRng};
+use rand_distr::{Distribution, StandardNormal};
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+#[derive(Debug)]
+pub enum SignalsError {
+    /// White noise bandwidth must be strictly positive
+    Bandwidth(f64),
+    /// Chirp duration must be strictly positive
+    Duration(f64),
+    /// Logarithmic chirp start and end frequencies must be strictly positive
+    Frequency(f64, f64),
+    /// PRBS order must be 2 to 16
+    Order(usize),
+    /// PRBS period must be strictly positive
+    Period(usize),
+}
+type Result<T> = std::result::Result<T, DOSError<SignalsError>>;
+
+/// Test signals
+#[derive(Debug, Clone)]
+pub enum Signal {
+    /// Constant value
+    Constant(f64),
+    /// Step of `amplitude` at `delay` seconds
+    Step { amplitude: f64, delay: f64 },
+    /// Sine wave with the `frequency` in Hz and the `phase` in radians
+    Sine {
+        amplitude: f64,
+        frequency: f64,
+        phase: f64,
+    },
+    /// Swept sine from `f_start` to `f_end` in Hz over `duration` seconds, repeated every `duration` seconds
+    ///
+    /// The frequency increases linearly with time or, if `logarithmic` is true, exponentially with time in which case both frequencies must be strictly positive
+    Chirp {
+        amplitude: f64,
+        f_start: f64,
+        f_end: f64,
+        duration: f64,
+        logarithmic: bool,
+    },
+    /// Gaussian white noise with the standard deviation `std`, low-pass filtered at the `bandwidth` in Hz
+    WhiteNoise { std: f64, bandwidth: f64 },
+    /// Maximum length pseudo-random binary sequence of $`\pm`$`amplitude`
+    ///
+    /// The sequence is generated with a linear feedback shift register of `order` bits (2 to 16) and each bit is held for `period` samples, the sequence repeats every $`(2^{order}-1)\times`$`period` samples
+    Prbs {
+        amplitude: f64,
+        order: usize,
+        period: usize,
+    },
+}
+
+impl Signal {
+    /// Checks the signal parameters
+    fn check(&self) -> Result<()> {
+        match *self {
+            Signal::WhiteNoise { bandwidth, .. } if bandwidth <= 0. || bandwidth.is_nan() => {
+                Err(DOSError::Component(SignalsError::Bandwidth(bandwidth)))
+            }
+            Signal::Chirp { duration, .. } if duration <= 0. || duration.is_nan() => {
+                Err(DOSError::Component(SignalsError::Duration(duration)))
+            }
+            Signal::Chirp {
+                f_start,
+                f_end,
+                logarithmic: true,
+                ..
+            } if !(f_start > 0. && f_end > 0.) => {
+                Err(DOSError::Component(SignalsError::Frequency(f_start, f_end)))
+            }
+            Signal::Prbs { order, .. } if !(2..=16).contains(&order) => {
+                Err(DOSError::Component(SignalsError::Order(order)))
+            }
+            Signal::Prbs { period: 0, .. } => Err(DOSError::Component(SignalsError::Period(0))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Maximum length feedback taps of the linear feedback shift registers of order 2 to 16
+const LFSR_TAPS: [&[u32]; 15] = [
+    &[2, 1],
+    &[3, 2],
+    &[4, 3],
+    &[5, 3],
+    &[6, 5],
+    &[7, 6],
+    &[8, 6, 5, 4],
+    &[9, 5],
+    &[10, 7],
+    &[11, 9],
+    &[12, 6, 4, 1],
+    &[13, 4, 3, 1],
+    &[14, 5, 3, 1],
+    &[15, 14],
+    &[16, 15, 13, 4],
+];
+
+/// Signal channel state
+struct Channel {
+    signal: Signal,
+    seed: u64,
+    rng: StdRng,
+    fir: Vec<f64>,
+    history: VecDeque<f64>,
+    lfsr: u32,
+}
+impl Channel {
+    fn new(signal: Signal, sampling_rate: f64, seed: u64) -> Self {
+        let fir = match &signal {
+            Signal::WhiteNoise { bandwidth, .. } if 2. * bandwidth < sampling_rate => {
+                let n_taps = 2 * (4. * sampling_rate / bandwidth).ceil() as usize + 1;
+                let h = filters::lowpass(bandwidth / sampling_rate, n_taps);
+                let norm = h.iter().map(|h| h * h).sum::<f64>().sqrt();
+                h.into_iter().map(|h| h / norm).collect()
+            }
+            _ => vec![1f64],
+        };
+        let lfsr = match &signal {
+            Signal::Prbs { order, .. } => (1u32 << order) - 1,
+            _ => 0,
+        };
+        Self {
+            signal,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            history: VecDeque::from(vec![0f64; fir.len()]),
+            fir,
+            lfsr,
+        }
+    }
+    /// Returns the signal value at sample `step`
+    fn sample(&mut self, step: usize, sampling_rate: f64) -> f64 {
+        let t = step as f64 / sampling_rate;
+        match self.signal {
+            Signal::Constant(value) => value,
+            Signal::Step { amplitude, delay } => {
+                if t >= delay {
+                    amplitude
+                } else {
+                    0f64
+                }
+            }
+            Signal::Sine {
+                amplitude,
+                frequency,
+                phase,
+            } => amplitude * (2. * PI * frequency * t + phase).sin(),
+            Signal::Chirp {
+                amplitude,
+                f_start,
+                f_end,
+                duration,
+                logarithmic,
+            } => {
+                let t = t % duration;
+                let phase = if logarithmic && f_end != f_start {
+                    let k = f_end / f_start;
+                    f_start * duration / k.ln() * (k.powf(t / duration) - 1.)
+                } else {
+                    f_start * t + 0.5 * (f_end - f_start) * t * t / duration
+                };
+                amplitude * (2. * PI * phase).sin()
+            }
+            Signal::WhiteNoise { std, .. } => {
+                let w: f64 = StandardNormal.sample(&mut self.rng);
+                self.history.pop_back();
+                self.history.push_front(w);
+                std * self
+                    .fir
+                    .iter()
+                    .zip(self.history.iter())
+                    .map(|(h, w)| h * w)
+                    .sum::<f64>()
+            }
+            Signal::Prbs {
+                amplitude,
+                order,
+                period,
+            } => {
+                if step > 0 && step.checked_rem(period) == Some(0) {
+                    let bit = LFSR_TAPS[order - 2]
+                        .iter()
+                        .fold(0u32, |b, tap| b ^ (self.lfsr >> (tap - 1)));
+                    self.lfsr = ((self.lfsr << 1) | (bit & 1)) & ((1u32 << order) - 1);
+                }
+                if self.lfsr & 1 == 1 {
+                    amplitude
+                } else {
+                    -amplitude
+                }
+            }
+        }
+    }
+}
+
+/// Signal generator builder
+pub struct Signals {
+    sampling_rate: f64,
+    n_sample: Option<usize>,
+    seed: u64,
+    outputs: Vec<(Tags, Vec<Signal>)>,
+}
+impl Signals {
+    /// Creates a new signal generator builder with the `sampling_rate` in Hz
+    pub fn new(sampling_rate: f64) -> Self {
+        Self {
+            sampling_rate,
+            n_sample: None,
+            seed: 0,
+            outputs: vec![],
+        }
+    }
+    /// Sets the number of samples, the signals never end if it is not set
+    pub fn n_sample(self, n_sample: usize) -> Self {
+        Self {
+            n_sample: Some(n_sample),
+            ..self
+        }
+    }
+    /// Sets the seed of the random signals
+    pub fn seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+    /// Outputs the same `signal` on the `size` channels of the `IO` `tag`
+    ///
+    /// The random signals are independent from one channel to the other
+    pub fn output(self, tag: Tags, size: usize, signal: Signal) -> Self {
+        let mut outputs = self.outputs;
+        match outputs.iter_mut().find(|(t, _)| *t == tag) {
+            Some((_, signals)) => *signals = vec![signal; size],
+            None => outputs.push((tag, vec![signal; size])),
+        }
+        Self { outputs, ..self }
+    }
+    /// Outputs `signal` on the channel `channel` of the `IO` `tag` of size `size`
+    ///
+    /// The other channels are set to 0 unless they have already been given a signal
+    pub fn output_channel(self, tag: Tags, size: usize, channel: usize, signal: Signal) -> Self {
+        assert!(channel < size, "channel must be less than size ({})", size);
+        let mut outputs = self.outputs;
+        let signals = match outputs.iter().position(|(t, _)| *t == tag) {
+            Some(k) => &mut outputs[k].1,
+            None => {
+                outputs.push((tag, vec![]));
+                &mut outputs.last_mut().unwrap().1
+            }
+        };
+        signals.resize(size, Signal::Constant(0f64));
+        signals[channel] = signal;
+        Self { outputs, ..self }
+    }
+    /// Builds a signal generator source object
+    ///
+    /// Returns an error if a white noise bandwidth or a chirp duration is not strictly positive
+    pub fn build(self) -> Result<SignalGenerator> {
+        self.outputs
+            .iter()
+            .flat_map(|(_, signals)| signals.iter())
+            .try_for_each(|signal| signal.check())?;
+        let sampling_rate = self.sampling_rate;
+        let mut seed = self.seed;
+        let outputs = self
+            .outputs
+            .into_iter()
+            .map(|(tag, signals)| {
+                let channels = signals
+                    .into_iter()
+                    .map(|signal| {
+                        seed += 1;
+                        Channel::new(signal, sampling_rate, seed)
+                    })
+                    .collect();
+                (tag, channels)
+            })
+            .collect();
+        Ok(SignalGenerator {
+            sampling_rate,
+            n_sample: self.n_sample,
+            step: 0,
+            outputs,
+        })
+    }
+}
+
+/// Signal generator source
+pub struct SignalGenerator {
+    pub sampling_rate: f64,
+    pub n_sample: Option<usize>,
+    step: usize,
+    outputs: Vec<(Tags, Vec<Channel>)>,
+}
+impl IOTags for SignalGenerator {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        self.outputs.iter().map(|(tag, _)| tag.clone()).collect()
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        vec![]
+    }
+}
+impl DOS for SignalGenerator {
+    fn inputs(
+        &mut self,
+        _: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        Err("SignalGenerator takes no inputs".into())
+    }
+    /// Restarts the signals, the random signals are the same than the first time
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        let sampling_rate = self.sampling_rate;
+        self.outputs
+            .iter_mut()
+            .flat_map(|(_, channels)| channels.iter_mut())
+            .for_each(|channel| {
+                *channel = Channel::new(channel.signal.clone(), sampling_rate, channel.seed)
+            });
+        self.step = 0;
+        Ok(self)
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        if matches!(self.n_sample, Some(n) if self.step >= n) {
+            return None;
+        }
+        let (step, sampling_rate) = (self.step, self.sampling_rate);
+        let outputs = self
+            .outputs
+            .iter_mut()
+            .map(|(tag, channels)| {
+                let data: Vec<f64> = channels
+                    .iter_mut()
+                    .map(|channel| channel.sample(step, sampling_rate))
+                    .collect();
+                (&*tag, data).into()
+            })
+            .collect();
+        self.step += 1;
+        Some(outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::jar;
+
+    #[test]
+    fn prbs_period() {
+        let order = 7;
+        let mut prbs = Signals::new(1e3)
+            .output(
+                jar::MountCmd::new(),
+                1,
+                Signal::Prbs {
+                    amplitude: 1.,
+                    order,
+                    period: 1,
+                },
+            )
+            .build()
+            .unwrap();
+        let n = (1 << order) - 1;
+        let x: Vec<f64> = (0..2 * n)
+            .map(|_| Option::<Vec<f64>>::from(&prbs.outputs().unwrap()[0]).unwrap()[0])
+            .collect();
+        assert_eq!(x[..n], x[n..]);
+        assert!((1..n).all(|p| (0..n).any(|i| x[i] != x[(i + p) % n])));
+        assert_eq!(x[..n].iter().filter(|x| **x > 0.).count(), 1 << (order - 1));
+    }
+
+    #[test]
+    fn invalid_signals() {
+        let signals = |signal: Signal| {
+            Signals::new(1e3)
+                .output(jar::MountCmd::new(), 1, signal)
+                .build()
+        };
+        assert!(signals(Signal::WhiteNoise {
+            std: 1.,
+            bandwidth: 0.
+        })
+        .is_err());
+        assert!(signals(Signal::Chirp {
+            amplitude: 1.,
+            f_start: 1.,
+            f_end: 10.,
+            duration: 0.,
+            logarithmic: false
+        })
+        .is_err());
+        for (f_start, f_end) in &[(0., 10.), (-1., 10.), (1., -10.)] {
+            assert!(signals(Signal::Chirp {
+                amplitude: 1.,
+                f_start: *f_start,
+                f_end: *f_end,
+                duration: 1.,
+                logarithmic: true
+            })
+            .is_err());
+        }
+        assert!(signals(Signal::Chirp {
+            amplitude: 1.,
+            f_start: 0.,
+            f_end: 10.,
+            duration: 1.,
+            logarithmic: false
+        })
+        .is_ok());
+        for (order, period) in &[(1, 1), (17, 1), (8, 0)] {
+            assert!(signals(Signal::Prbs {
+                amplitude: 1.,
+                order: *order,
+                period: *period
+            })
+            .is_err());
+        }
+        assert!(signals(Signal::WhiteNoise {
+            std: 1.,
+            bandwidth: 10.
+        })
+        .is_ok());
+    }
+}
@@ -1,70 +1,237 @@
-use crate::{io::IO, DOSError};
-use std::collections::BTreeMap;
+//! This module is used to log the data flowing between the components of an end-to-end model
+//!
+//! The data logger [`TellTale`] is built with [`DataLogging`] and, as any other component, implements the [`DOS`], [`IOTags`] and [`Iterator`] traits.
+//! The data passed to [`inputs`](crate::DOS::inputs) are logged at the current time index and the time index is incremented with [`step`](crate::DOS::step).
+//!
+//! For long runs, the data logger can be set to capture only the samples surrounding some events.
+//! In that mode, the last samples are kept in a ring buffer and a window of samples before and after each [`Trigger`] is saved into a [`Capture`].
+//! A trigger within the window of a capture extends the capture instead of starting a new one.
+//!
+//! # Example
+//! Capturing 100 samples before and 400 samples after the azimuth drive torque exceeds 1kN.m
+//! ```no_run
+//! use dos::{io::jar, telltale::Trigger, DataLogging};
+//!
+//! let mut data = DataLogging::new()
+//!     .sampling_rate(1e3)
+//!     .key(jar::OSSM1Lcl::new())
+//!     .capture(100, 400)
+//!     .trigger(Trigger::above(jar::OSSAzDriveTorque::new(), 1e3))
+//!     .build();
+//! ```
+
+use crate::{
+    io::{Tags, IO},
+    DOSError, IOTags, DOS,
+};
+use std::collections::{BTreeMap, VecDeque};
 
 #[derive(Debug)]
 pub enum TellTaleError {
-    Step,
-    Tale,
+    Tale(Tags),
 }
 type Result<T> = std::result::Result<T, DOSError<TellTaleError>>;
+type Entries = BTreeMap<usize, Vec<IO<Vec<f64>>>>;
+type Condition = Box<dyn Fn(&[f64]) -> bool>;
+
+/// Trigger condition on a logged `IO`
+pub struct Trigger {
+    key: Tags,
+    condition: Condition,
+}
+impl Trigger {
+    /// Creates a trigger on `key` that fires when `condition` is true
+    pub fn new<F>(key: Tags, condition: F) -> Self
+    where
+        F: Fn(&[f64]) -> bool + 'static,
+    {
+        Self {
+            key,
+            condition: Box::new(condition),
+        }
+    }
+    /// Creates a trigger on `key` that fires when the magnitude of any element is greater than `threshold`
+    pub fn above(key: Tags, threshold: f64) -> Self {
+        Self::new(key, move |data| data.iter().any(|x| x.abs() > threshold))
+    }
+    fn is_triggered(&self, sample: &[IO<Vec<f64>>]) -> bool {
+        match sample
+            .iter()
+            .find(|tale| self.key == **tale)
+            .and_then(Option::<Vec<f64>>::from)
+        {
+            Some(data) => (self.condition)(&data),
+            None => false,
+        }
+    }
+}
+
+/// Samples captured around a trigger
+#[derive(Debug, Default)]
+pub struct Capture {
+    /// Time index of the first trigger
+    pub trigger: usize,
+    /// Captured samples
+    pub entries: Entries,
+}
+impl Capture {
+    /// Returns the time series of the `IO` corresponding to `key`
+    pub fn time_series(&self, key: IO<()>, sampling_rate: f64) -> IO<TimeSeries> {
+        time_series(&self.entries, key, sampling_rate)
+    }
+}
 
+/// Data logger
 pub struct TellTale {
     pub sampling_rate: f64,
-    pub keys: Vec<IO<()>>,
-    pub entries: BTreeMap<usize, Vec<IO<Vec<f64>>>>,
-    index: Option<usize>,
+    pub keys: Vec<Tags>,
+    pub entries: Entries,
+    /// Samples captured around the triggers
+    pub captures: Vec<Capture>,
+    index: usize,
+    window: Option<(usize, usize)>,
+    triggers: Vec<Trigger>,
+    ring: VecDeque<(usize, Vec<IO<Vec<f64>>>)>,
+    countdown: usize,
 }
 impl TellTale {
-    pub fn step(&mut self) -> Result<&mut Self>
-    where
-        Self: Sized + Iterator,
-    {
-        self.next()
-            .and(Some(self))
-            .ok_or_else(|| DOSError::Component(TellTaleError::Step))
+    /// Returns the current time index
+    pub fn index(&self) -> usize {
+        self.index
     }
+    /// Logs a single `IO` at the current time index
     pub fn log(&mut self, tale: &IO<Vec<f64>>) -> Result<&mut Self> {
-        self.index
-            .and_then(|i| {
-                self.entries.entry(i).or_default().push(tale.clone());
-                Some(())
-            })
-            .ok_or(DOSError::Component(TellTaleError::Tale))?;
+        if Option::<Vec<f64>>::from(tale).is_none() {
+            return Err(DOSError::Component(TellTaleError::Tale(tale.into())));
+        }
+        self.entries
+            .entry(self.index)
+            .or_default()
+            .push(tale.clone());
         Ok(self)
     }
+    /// Returns the time series of the `IO` corresponding to `key`
     pub fn time_series(&self, key: IO<()>) -> IO<TimeSeries> {
-        let tau = self.sampling_rate.recip();
-        (
-            &key,
-            self.entries
-                .iter()
-                .map(|(index, data)| {
-                    data.iter()
-                        .find_map(|d| if key == *d { d.into() } else { None })
-                        .and_then(|x| Some((*index as f64 * tau, x)))
-                })
-                .collect(),
-        )
-            .into()
+        time_series(&self.entries, key, self.sampling_rate)
+    }
+    /// Returns the time series of the `IO` corresponding to `key` for each capture
+    pub fn captured_time_series(&self, key: IO<()>) -> Vec<IO<TimeSeries>> {
+        self.captures
+            .iter()
+            .map(|capture| capture.time_series(key.clone(), self.sampling_rate))
+            .collect()
+    }
+    // Moves the current sample into the ring buffer and into the captures
+    fn capture(&mut self, n_pre: usize, n_post: usize) {
+        let sample = self.entries.remove(&self.index).unwrap_or_default();
+        let triggered = self.triggers.iter().any(|t| t.is_triggered(&sample));
+        if self.countdown > 0 {
+            if let Some(capture) = self.captures.last_mut() {
+                capture.entries.insert(self.index, sample.clone());
+            }
+            self.countdown = if triggered {
+                n_post
+            } else {
+                self.countdown - 1
+            };
+        } else if triggered {
+            let mut entries: Entries = self.ring.iter().cloned().collect();
+            entries.insert(self.index, sample.clone());
+            self.captures.push(Capture {
+                trigger: self.index,
+                entries,
+            });
+            self.countdown = n_post;
+        }
+        if n_pre > 0 {
+            if self.ring.len() == n_pre {
+                self.ring.pop_front();
+            }
+            self.ring.push_back((self.index, sample));
+        }
     }
 }
+fn time_series(entries: &Entries, key: IO<()>, sampling_rate: f64) -> IO<TimeSeries> {
+    let tau = sampling_rate.recip();
+    (
+        &key,
+        entries
+            .iter()
+            .map(|(index, data)| {
+                data.iter()
+                    .find_map(|d| if key == *d { d.into() } else { None })
+                    .map(|x| (*index as f64 * tau, x))
+            })
+            .collect(),
+    )
+        .into()
+}
 pub type TimeSeries = Vec<(f64, Vec<f64>)>;
 impl Iterator for TellTale {
     type Item = ();
     fn next(&mut self) -> Option<Self::Item> {
-        self.index = self.index.map_or(Some(0), |x| Some(x + 1));
+        if let Some((n_pre, n_post)) = self.window {
+            self.capture(n_pre, n_post);
+        }
+        self.index += 1;
         Some(())
     }
 }
+impl IOTags for TellTale {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        vec![]
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        self.keys.clone()
+    }
+}
+impl DOS for TellTale {
+    /// Logs the data at the current time index
+    ///
+    /// If the data logger has been given some keys, only the data matching the keys are logged
+    fn inputs(
+        &mut self,
+        data: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        for tale in data {
+            if self.keys.is_empty() || self.keys.iter().any(|key| *key == tale) {
+                self.log(&tale)?;
+            }
+        }
+        Ok(self)
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        None
+    }
+    /// Clears the logged data and the captures
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        self.entries.clear();
+        self.captures.clear();
+        self.ring.clear();
+        self.index = 0;
+        self.countdown = 0;
+        Ok(self)
+    }
+}
+/// Data logger builder
 pub struct DataLogging {
     pub sampling_rate: f64,
     pub keys: Vec<IO<()>>,
+    window: Option<(usize, usize)>,
+    triggers: Vec<Trigger>,
+}
+impl Default for DataLogging {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 impl DataLogging {
     pub fn new() -> Self {
         Self {
             sampling_rate: 1f64,
             keys: vec![],
+            window: None,
+            triggers: vec![],
         }
     }
     pub fn sampling_rate(self, sampling_rate: f64) -> Self {
@@ -78,12 +245,102 @@ impl DataLogging {
         keys.push(key);
         Self { keys, ..self }
     }
+    /// Sets the keys from the outputs of another component
+    pub fn keys_from(self, element: &dyn IOTags) -> Self {
+        let mut keys = self.keys;
+        keys.extend(element.outputs_tags());
+        Self { keys, ..self }
+    }
+    /// Logs only the `n_pre` samples before and the `n_post` samples after each trigger
+    pub fn capture(self, n_pre: usize, n_post: usize) -> Self {
+        Self {
+            window: Some((n_pre, n_post)),
+            ..self
+        }
+    }
+    /// Adds a capture trigger
+    ///
+    /// The trigger `IO` is logged with the other keys
+    pub fn trigger(self, trigger: Trigger) -> Self {
+        let mut triggers = self.triggers;
+        triggers.push(trigger);
+        Self { triggers, ..self }
+    }
     pub fn build(self) -> TellTale {
+        let mut keys = self.keys;
+        if !keys.is_empty() {
+            for trigger in self.triggers.iter() {
+                if !keys.contains(&trigger.key) {
+                    keys.push(trigger.key.clone());
+                }
+            }
+        }
         TellTale {
             sampling_rate: self.sampling_rate,
-            keys: self.keys,
+            keys,
             entries: BTreeMap::new(),
-            index: None,
+            captures: vec![],
+            index: 0,
+            window: self.window,
+            triggers: self.triggers,
+            ring: VecDeque::new(),
+            countdown: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::jar;
+
+    #[test]
+    fn telltale_capture() {
+        let mut data = DataLogging::new()
+            .capture(2, 3)
+            .trigger(Trigger::above(jar::MountCmd::new(), 0.5))
+            .build();
+        for k in 0..20 {
+            let x = if k == 5 || k == 14 { 1f64 } else { 0f64 };
+            data.inputs(vec![jar::MountCmd::with(vec![x; 3])])
+                .unwrap()
+                .step()
+                .unwrap();
+        }
+        assert!(data.entries.is_empty());
+        let indices: Vec<Vec<usize>> = data
+            .captures
+            .iter()
+            .map(|c| c.entries.keys().cloned().collect())
+            .collect();
+        assert_eq!(
+            indices,
+            vec![vec![3, 4, 5, 6, 7, 8], vec![12, 13, 14, 15, 16, 17]]
+        );
+    }
+
+    #[test]
+    fn telltale_capture_extended() {
+        let mut data = DataLogging::new()
+            .capture(2, 3)
+            .trigger(Trigger::above(jar::MountCmd::new(), 0.5))
+            .build();
+        for k in 0..20 {
+            let x = if k == 5 || k == 7 { 1f64 } else { 0f64 };
+            data.inputs(vec![jar::MountCmd::with(vec![x; 3])])
+                .unwrap()
+                .step()
+                .unwrap();
         }
+        assert_eq!(data.captures.len(), 1);
+        assert_eq!(data.captures[0].trigger, 5);
+        assert_eq!(
+            data.captures[0]
+                .entries
+                .keys()
+                .cloned()
+                .collect::<Vec<usize>>(),
+            (3..=10).collect::<Vec<usize>>()
+        );
     }
 }
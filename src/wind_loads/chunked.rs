@@ -0,0 +1,390 @@
+//! This module is used to stream the wind loads from a memory-mapped data store
+//!
+//! Reading the CFD wind loads from a pickle file requires to load all the load groups for the whole time range in memory.
+//! Instead, the wind loads can be converted once into a data store with [`WindLoads::to_chunked`] and later streamed with [`ChunkedWindLoads`].
+//! The files of the selected load groups are memory-mapped and the time samples are decoded one at a time,
+//! so only the pages within the selected time range are read from the disk.
+//!
+//! A data store is a directory with:
+//!  - an `index.pkl` pickle file with the time vector and the name and size of each load group,
+//!  - a `<load group name>.bin` file per load group with the forces and moments time series written sample after sample as little endian `f64`.
+//!
+//! # Example
+//! ```no_run
+//! use dos::{io::jar, wind_loads::chunked::ChunkedWindLoads, WindLoads, DOS};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     // Conversion from the pickle file, done only once
+//!     WindLoads::from_pickle("wind_loads_2kHz.pkl")?.to_chunked("wind_loads_2kHz")?;
+//!     // Streaming the truss and M1 segments loads
+//!     let mut wind_loading = ChunkedWindLoads::open("wind_loads_2kHz")?
+//!         .range(0.0, 400.0)?
+//!         .load(jar::OSSTruss6F::new())?
+//!         .load(jar::OSSM1Lcl6F::new())?
+//!         .build()?;
+//!     while let Some(fem_forces) = wind_loading.outputs() {
+//!         // ...
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use super::{range_indices, Loads, Result, WindLoads, WindLoadsError};
+use crate::{
+    io::{jar, Tags},
+    DOSError, IOTags, DOS, IO,
+};
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use serde_pickle as pkl;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Load group description
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChunkedGroup {
+    /// Name of the loads in the CFD data file
+    pub name: String,
+    /// Number of time samples
+    pub n_sample: usize,
+    /// Number of forces and moments per time sample
+    pub n_element: usize,
+}
+
+/// Data store index
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChunkedIndex {
+    /// time vector
+    pub time: Vec<f64>,
+    /// load groups
+    pub groups: Vec<ChunkedGroup>,
+}
+
+impl WindLoads {
+    /// Writes the wind loads into the data store `path`
+    pub fn to_chunked<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        fs::create_dir_all(path)?;
+        let mut groups = vec![];
+        for loads in self.loads.iter().filter_map(|x| x.as_ref()) {
+            let data = loads.as_slice();
+            let mut w =
+                BufWriter::new(File::create(path.join(loads.name()).with_extension("bin"))?);
+            for sample in data {
+                for value in sample {
+                    w.write_all(&value.to_le_bytes())?;
+                }
+            }
+            w.flush()?;
+            groups.push(ChunkedGroup {
+                name: loads.name().to_owned(),
+                n_sample: data.len(),
+                n_element: data.first().map_or(0, |x| x.len()),
+            });
+        }
+        let index = ChunkedIndex {
+            time: self.time.clone(),
+            groups,
+        };
+        let mut f = File::create(path.join("index.pkl"))?;
+        pkl::to_writer(&mut f, &index, true)?;
+        Ok(())
+    }
+}
+
+/// Chunked wind loads builder
+///
+/// This structure is used to select the load groups and the time range from a data store and to create the [`ChunkedWindLoading`] structure
+pub struct ChunkedWindLoads {
+    path: PathBuf,
+    index: ChunkedIndex,
+    min_index: usize,
+    max_index: usize,
+    n_sample: Option<usize>,
+    selection: Vec<(ChunkedGroup, Tags)>,
+}
+impl ChunkedWindLoads {
+    /// Opens a data store
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let f = File::open(path.join("index.pkl"))?;
+        let index: ChunkedIndex = pkl::from_reader(BufReader::new(f))?;
+        let max_index = index.time.len();
+        Ok(Self {
+            path,
+            index,
+            min_index: 0,
+            max_index,
+            n_sample: None,
+            selection: vec![],
+        })
+    }
+    /// Returns the time vector within the selected time range
+    pub fn time(&self) -> &[f64] {
+        &self.index.time[self.min_index..self.max_index]
+    }
+    /// Selects the time samples within the time range [`t_min`,`t_max`[
+    ///
+    /// Returns an error if `t_min` is not less than `t_max` or if the time range does not overlap the record
+    pub fn range(self, t_min: f64, t_max: f64) -> Result<Self> {
+        let (min_index, max_index) = range_indices(&self.index.time, t_min, t_max)?;
+        Ok(Self {
+            min_index,
+            max_index,
+            ..self
+        })
+    }
+    /// Set the number of time sample
+    pub fn n_sample(self, n_sample: usize) -> Result<Self> {
+        let n = self.max_index - self.min_index;
+        if n_sample == 0 || n_sample > n {
+            return Err(DOSError::Component(WindLoadsError::Range(format!(
+                "n_sample ({}) must be in [1,{}]",
+                n_sample, n
+            ))));
+        }
+        Ok(Self {
+            n_sample: Some(n_sample),
+            ..self
+        })
+    }
+    /// Selects the load group `group`
+    pub fn load(self, group: Tags) -> Result<Self> {
+        let input = group.clone();
+        self.load_as(group, input)
+    }
+    /// Selects the load group `group` and applies it to the FEM input `input`
+    pub fn load_as(mut self, group: Tags, input: Tags) -> Result<Self> {
+        let name = Loads::name_of(&group).map_or(format!("{:?}", group), |name| name.to_owned());
+        let group = self
+            .index
+            .groups
+            .iter()
+            .find(|g| g.name == name)
+            .cloned()
+            .ok_or(DOSError::Component(WindLoadsError::Missing(name)))?;
+        self.selection.push((group, input));
+        Ok(self)
+    }
+    /// Selects all loads
+    pub fn select_all(self) -> Result<Self> {
+        self.load(jar::OSSTopEnd6F::new())?
+            .load(jar::MCM2Lcl6F::new())?
+            .load(jar::OSSTruss6F::new())?
+            .load(jar::OSSM1Lcl6F::new())?
+            .load(jar::OSSCellLcl6F::new())?
+            .load(jar::OSSGIR6F::new())?
+            .load(jar::OSSCRING6F::new())
+    }
+    /// Selects all loads in the ASM configuration
+    pub fn select_all_with_asm(self) -> Result<Self> {
+        self.load_as(jar::OSSTopEnd6F::new(), jar::MCM2TE6F::new())?
+            .load_as(jar::MCM2Lcl6F::new(), jar::MCM2RB6F::new())?
+            .load(jar::OSSTruss6F::new())?
+            .load(jar::OSSM1Lcl6F::new())?
+            .load(jar::OSSCellLcl6F::new())?
+            .load(jar::OSSGIR6F::new())?
+            .load(jar::OSSCRING6F::new())
+    }
+    /// Builds a chunked wind loading source object
+    ///
+    /// Returns an error if a load group file is shorter than the selected time range
+    pub fn build(self) -> Result<ChunkedWindLoading> {
+        let n_sample = self.n_sample.unwrap_or(self.max_index - self.min_index);
+        let loads = self
+            .selection
+            .iter()
+            .map(|(group, input)| {
+                let n = n_sample.min(group.n_sample.saturating_sub(self.min_index));
+                let reader = ChunkReader::open(
+                    self.path.join(&group.name).with_extension("bin"),
+                    group.n_element,
+                    self.min_index,
+                    n,
+                )?;
+                Ok((input, Some(reader)).into())
+            })
+            .collect::<Result<Vec<IO<ChunkReader>>>>()?;
+        Ok(ChunkedWindLoading { loads, n_sample })
+    }
+}
+
+/// Load group reader
+///
+/// Decodes the time samples of a load group from the memory-mapped load group file
+pub struct ChunkReader {
+    map: Mmap,
+    n_element: usize,
+    start: usize,
+    n_sample: usize,
+    step: usize,
+}
+impl ChunkReader {
+    fn open<P: AsRef<Path>>(
+        path: P,
+        n_element: usize,
+        start: usize,
+        n_sample: usize,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let size = (start + n_sample) * n_element * 8;
+        let len = file.metadata()?.len() as usize;
+        if len < size {
+            return Err(DOSError::Component(WindLoadsError::Format(format!(
+                "{:?} has {} bytes, expected at least {}",
+                path, len, size
+            ))));
+        }
+        // The data store files are only written by `WindLoads::to_chunked`, they must not be modified while they are mapped
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self {
+            map,
+            n_element,
+            start,
+            n_sample,
+            step: 0,
+        })
+    }
+    /// Moves back to the first time sample
+    fn rewind(&mut self) {
+        self.step = 0;
+    }
+}
+impl Iterator for ChunkReader {
+    type Item = Vec<f64>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.step == self.n_sample {
+            return None;
+        }
+        let offset = (self.start + self.step) * self.n_element * 8;
+        self.step += 1;
+        Some(
+            self.map[offset..offset + self.n_element * 8]
+                .chunks(8)
+                .map(|b| {
+                    let mut x = [0u8; 8];
+                    x.copy_from_slice(b);
+                    f64::from_le_bytes(x)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Chunked wind loading sources
+///
+/// This structure streams the time series of wind forces and moments from a data store.
+/// It is used the same way than [`WindLoading`](crate::wind_loads::WindLoading)
+pub struct ChunkedWindLoading {
+    pub loads: Vec<IO<ChunkReader>>,
+    pub n_sample: usize,
+}
+
+/// Chunked wind loading interface
+impl IOTags for ChunkedWindLoading {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        self.loads.iter().map(|x| x.into()).collect()
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        vec![]
+    }
+}
+impl DOS for ChunkedWindLoading {
+    fn inputs(
+        &mut self,
+        _: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        Err("ChunkedWindLoading takes no inputs".into())
+    }
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        for reader in self.loads.iter_mut().filter_map(|x| x.as_mut()) {
+            reader.rewind();
+        }
+        Ok(self)
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        self.loads
+            .iter_mut()
+            .map(|x| -> Option<IO<Vec<f64>>> { x.into() })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunked_wind_loads_round_trip() {
+        let time: Vec<f64> = (0..50).map(|k| k as f64 * 0.1).collect();
+        let wind_loads = || {
+            WindLoads::new(
+                time.clone(),
+                vec![
+                    Loads::OSSTruss6F(
+                        (0..50)
+                            .map(|k| (0..6).map(|i| (k * 6 + i) as f64).collect())
+                            .collect(),
+                    ),
+                    Loads::OSSGIR6F(vec![vec![-1f64; 6]; 50]),
+                ],
+            )
+        };
+        let path = std::env::temp_dir().join(format!("dos-chunked-{}", std::process::id()));
+        wind_loads().to_chunked(&path).unwrap();
+        let mut chunked = ChunkedWindLoads::open(&path)
+            .unwrap()
+            .range(1.0, 2.5)
+            .unwrap()
+            .load(jar::OSSTruss6F::new())
+            .unwrap()
+            .build()
+            .unwrap();
+        let mut in_memory = wind_loads()
+            .range(1.0, 2.5)
+            .unwrap()
+            .truss()
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(chunked.n_sample, 15);
+        for _ in 0..2 {
+            let mut n = 0;
+            while let Some(y) = chunked.outputs() {
+                let x = in_memory.outputs().unwrap();
+                assert_eq!(y.len(), 1);
+                assert_eq!(
+                    Option::<Vec<f64>>::from(&y[jar::OSSTruss6F::new()]),
+                    Option::<Vec<f64>>::from(&x[jar::OSSTruss6F::new()])
+                );
+                n += 1;
+            }
+            assert_eq!(n, 15);
+            assert!(in_memory.outputs().is_none());
+            chunked.reset().unwrap();
+            in_memory = wind_loads()
+                .range(1.0, 2.5)
+                .unwrap()
+                .truss()
+                .unwrap()
+                .build()
+                .unwrap();
+        }
+        assert!(ChunkedWindLoads::open(&path)
+            .unwrap()
+            .range(2.0, 1.0)
+            .is_err());
+        assert!(ChunkedWindLoads::open(&path).unwrap().n_sample(51).is_err());
+        fs::remove_file(path.join("OSS_Truss_6F.bin")).unwrap();
+        fs::write(path.join("OSS_Truss_6F.bin"), vec![0u8; 8]).unwrap();
+        assert!(ChunkedWindLoads::open(&path)
+            .unwrap()
+            .load(jar::OSSTruss6F::new())
+            .unwrap()
+            .build()
+            .is_err());
+        fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+//! Signal processing of the wind loads time series
+//!
+//! The decimation of the wind loads is performed with a low-pass windowed-sinc FIR filter
+//! with $`20r+1`$ coefficients and a cut-off frequency at the Nyquist frequency of the decimated time series,
+//! $`r`$ being the decimation rate (this is the same filter than `scipy.signal.decimate` with `ftype='fir'`).
+//! The filter is only evaluated at the decimated samples (polyphase implementation) and is centered on these samples, so the decimated time series is not delayed.
+//!
+//! The wind loads can also be resampled at any sampling rate with either a linear or a band-limited interpolation (see [`Interpolation`]).
+
+use rayon::prelude::*;
+use std::f64::consts::PI;
+
+/// Low-pass FIR filter coefficients
+///
+/// Windowed-sinc filter with a Hamming window, `n_taps` coefficients and the cut-off frequency `cutoff` normalized to the sampling frequency ($`0<`$ `cutoff` $`<0.5`$).
+/// The filter gain at zero frequency is 1.
+pub fn lowpass(cutoff: f64, n_taps: usize) -> Vec<f64> {
+    let m = (n_taps - 1) as f64;
+    let h: Vec<f64> = (0..n_taps)
+        .map(|k| {
+            let x = k as f64 - 0.5 * m;
+            let sinc = if x == 0f64 {
+                2. * cutoff
+            } else {
+                (2. * PI * cutoff * x).sin() / (PI * x)
+            };
+            let window = if m > 0f64 {
+                0.54 - 0.46 * (2. * PI * k as f64 / m).cos()
+            } else {
+                1f64
+            };
+            sinc * window
+        })
+        .collect();
+    let s: f64 = h.iter().sum();
+    h.into_iter().map(|h| h / s).collect()
+}
+
+/// Low-pass filters and downsamples the time series `x` by `rate`
+///
+/// At both ends of the time series, the filter coefficients falling outside the time series are discarded and the remaining ones are renormalized.
+pub fn decimate(x: &[Vec<f64>], rate: usize) -> Vec<Vec<f64>> {
+    if rate <= 1 || x.is_empty() {
+        return x.to_vec();
+    }
+    let n_taps = 20 * rate + 1;
+    let h = lowpass(0.5 / rate as f64, n_taps);
+    let half = (n_taps - 1) / 2;
+    let n = x.len();
+    let n_y = x[0].len();
+    (0..n)
+        .step_by(rate)
+        .collect::<Vec<usize>>()
+        .into_par_iter()
+        .map(|i| {
+            let k_min = (i + half + 1).saturating_sub(n);
+            let k_max = (i + half).min(n_taps - 1);
+            let mut y = vec![0f64; n_y];
+            let mut s = 0f64;
+            for k in k_min..=k_max {
+                let hk = h[k];
+                y.iter_mut()
+                    .zip(x[i + half - k].iter())
+                    .for_each(|(y, x)| *y += hk * x);
+                s += hk;
+            }
+            y.iter_mut().for_each(|y| *y /= s);
+            y
+        })
+        .collect()
+}
+
+/// Resampling interpolation methods
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    /// Linear interpolation between the 2 nearest samples
+    Linear,
+    /// Windowed-sinc interpolation
+    ///
+    /// The sinc kernel spans 16 zero crossings on each side of the interpolated sample and it is tapered with a Hann window.
+    /// When downsampling, the kernel is stretched to low-pass filter the time series at the new Nyquist frequency.
+    /// The original time series is assumed to be uniformly sampled.
+    BandLimited,
+}
+
+/// Resamples the time series `x`, sampled at `time`, at the time samples `new_time`
+///
+/// Samples of `new_time` outside the range of `time` are set to the nearest edge value of `x`
+pub fn resample(
+    time: &[f64],
+    x: &[Vec<f64>],
+    new_time: &[f64],
+    interpolation: Interpolation,
+) -> Vec<Vec<f64>> {
+    let n = time.len().min(x.len());
+    if n < 2 {
+        return new_time.iter().filter_map(|_| x.first().cloned()).collect();
+    }
+    match interpolation {
+        Interpolation::Linear => new_time
+            .par_iter()
+            .map(|t| {
+                let i = time[..n].partition_point(|ti| ti <= t);
+                if i == 0 {
+                    x[0].clone()
+                } else if i == n {
+                    x[n - 1].clone()
+                } else {
+                    let w = (t - time[i - 1]) / (time[i] - time[i - 1]);
+                    x[i - 1]
+                        .iter()
+                        .zip(x[i].iter())
+                        .map(|(x0, x1)| x0 + w * (x1 - x0))
+                        .collect()
+                }
+            })
+            .collect(),
+        Interpolation::BandLimited => {
+            const N_ZEROS: f64 = 16.;
+            let tau = (time[n - 1] - time[0]) / (n - 1) as f64;
+            let new_tau = if new_time.len() > 1 {
+                (new_time[new_time.len() - 1] - new_time[0]) / (new_time.len() - 1) as f64
+            } else {
+                tau
+            };
+            let fc = (tau / new_tau).min(1f64);
+            let half_width = N_ZEROS / fc;
+            let n_y = x[0].len();
+            new_time
+                .par_iter()
+                .map(|t| {
+                    let u = ((t - time[0]) / tau).max(0f64).min((n - 1) as f64);
+                    let j_min = (u - half_width).ceil().max(0f64) as usize;
+                    let j_max = ((u + half_width).floor() as usize).min(n - 1);
+                    let mut y = vec![0f64; n_y];
+                    let mut s = 0f64;
+                    for j in j_min..=j_max {
+                        let d = u - j as f64;
+                        let arg = PI * fc * d;
+                        let sinc = if arg == 0f64 { 1f64 } else { arg.sin() / arg };
+                        let window = 0.5 * (1. + (PI * d / half_width).cos());
+                        let w = sinc * window;
+                        y.iter_mut()
+                            .zip(x[j].iter())
+                            .for_each(|(y, x)| *y += w * x);
+                        s += w;
+                    }
+                    y.iter_mut().for_each(|y| *y /= s);
+                    y
+                })
+                .collect()
+        }
+    }
+}
+
+/// Cross-fades the last `n` samples of the time series `x` into the first `n` samples with a raised cosine
+///
+/// The time series is shortened by `n` samples, so the last sample is followed seamlessly by the first one when looping over the time series
+pub fn crossfade(x: &mut Vec<Vec<f64>>, n: usize) {
+    let n = n.min(x.len() / 2);
+    if n == 0 {
+        return;
+    }
+    let tail = x.split_off(x.len() - n);
+    x.iter_mut().zip(tail).enumerate().for_each(|(i, (x, y))| {
+        let w = 0.5 * (1. - (PI * (i as f64 + 0.5) / n as f64).cos());
+        x.iter_mut()
+            .zip(y)
+            .for_each(|(x, y)| *x = w * *x + (1. - w) * y);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimate_constant_and_alias() {
+        let rate = 2;
+        let n = 2000;
+        let x: Vec<Vec<f64>> = (0..n)
+            .map(|k| {
+                let alias = (2. * PI * 0.4 * k as f64).sin();
+                vec![1f64, alias]
+            })
+            .collect();
+        let y = decimate(&x, rate);
+        assert_eq!(y.len(), n / rate);
+        assert!(y.iter().all(|y| (y[0] - 1.).abs() < 1e-12));
+        let rms = (y[100..900].iter().map(|y| y[1] * y[1]).sum::<f64>() / 800.).sqrt();
+        assert!(rms < 1e-2, "aliased rms: {}", rms);
+    }
+
+    #[test]
+    fn resample_sine() {
+        let time: Vec<f64> = (0..2000).map(|k| k as f64 / 2e3).collect();
+        let x: Vec<Vec<f64>> = time.iter().map(|t| vec![(2. * PI * 5. * t).sin()]).collect();
+        let new_time: Vec<f64> = (0..1500).map(|k| k as f64 / 1.5e3).collect();
+        for interpolation in [Interpolation::Linear, Interpolation::BandLimited].iter() {
+            let y = resample(&time, &x, &new_time, *interpolation);
+            assert_eq!(y.len(), new_time.len());
+            let err = y[100..1400]
+                .iter()
+                .zip(new_time[100..1400].iter())
+                .map(|(y, t)| (y[0] - (2. * PI * 5. * t).sin()).abs())
+                .fold(0f64, f64::max);
+            assert!(err < 1e-3, "{:?} error: {}", interpolation, err);
+        }
+    }
+}
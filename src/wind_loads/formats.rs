@@ -0,0 +1,220 @@
+//! This module is used to read the wind loads from other data file formats than pickle
+//!
+//! The data file format is selected according to the file extension with [`WindLoads::from_file`]:
+//!  - `.pkl` or `.pickle`: see [`WindLoads::from_pickle`],
+//!  - `.csv`: see [`WindLoads::from_csv`],
+//!  - `.npz`: see [`WindLoads::from_npz`].
+//!
+//! In all cases, the load groups are identified with the same names than in the pickle files (see [`Loads::names`]).
+
+use super::{Loads, Result, WindLoads, WindLoadsError};
+use crate::{formats::read_npy, DOSError};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+impl WindLoads {
+    /// Reads the wind loads from a data file, the file format is given by the file extension
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match path.extension().and_then(|x| x.to_str()) {
+            Some("pkl") | Some("pickle") => Self::from_pickle(path),
+            Some("csv") => Self::from_csv(path),
+            Some("npz") => Self::from_npz(path),
+            _ => Err(DOSError::Component(WindLoadsError::Format(format!(
+                "unsupported wind loads file: {:?}",
+                path
+            )))),
+        }
+    }
+    /// Reads the wind loads from a CSV file
+    ///
+    /// The first row of the file is the header with a `time` column and one column per force or moment component.
+    /// A component column is named `<load group name>_<component index>`, e.g. `OSS_Truss_6F_0`,
+    /// the component indices of a load group must be unique and contiguous from 0, in any column order.
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut reader = csv::Reader::from_path(path)?;
+        let mut time_column = None;
+        let mut columns: BTreeMap<&'static str, Vec<(usize, usize)>> = BTreeMap::new();
+        for (k, header) in reader.headers()?.iter().enumerate() {
+            let header = header.trim();
+            if header == "time" {
+                time_column = Some(k);
+                continue;
+            }
+            let (name, component) = Loads::names()
+                .into_iter()
+                .find_map(|name| {
+                    header
+                        .strip_prefix(name)
+                        .and_then(|x| x.strip_prefix('_'))
+                        .and_then(|x| x.parse::<usize>().ok())
+                        .map(|component| (name, component))
+                })
+                .ok_or_else(|| {
+                    DOSError::Component(WindLoadsError::Format(format!(
+                        "unknown CSV column: {}",
+                        header
+                    )))
+                })?;
+            columns.entry(name).or_default().push((component, k));
+        }
+        let time_column = time_column
+            .ok_or_else(|| DOSError::Component(WindLoadsError::Missing("time".to_owned())))?;
+        for (name, c) in columns.iter_mut() {
+            c.sort_unstable();
+            if let Some((k, (component, _))) = c
+                .iter()
+                .enumerate()
+                .find(|(k, (component, _))| k != component)
+            {
+                return Err(DOSError::Component(WindLoadsError::Format(format!(
+                    "{} CSV columns: expected component index {}, found {}",
+                    name, k, component
+                ))));
+            }
+        }
+        let mut time = vec![];
+        let mut data: BTreeMap<&'static str, Vec<Vec<f64>>> = BTreeMap::new();
+        for record in reader.records() {
+            let record = record?;
+            let value = |k: usize| -> Result<f64> {
+                record
+                    .get(k)
+                    .and_then(|x| x.trim().parse::<f64>().ok())
+                    .ok_or_else(|| {
+                        DOSError::Component(WindLoadsError::Format(format!(
+                            "invalid CSV record: {:?}",
+                            record
+                        )))
+                    })
+            };
+            time.push(value(time_column)?);
+            for (name, c) in columns.iter() {
+                let sample = c
+                    .iter()
+                    .map(|(_, k)| value(*k))
+                    .collect::<Result<Vec<f64>>>()?;
+                data.entry(name).or_default().push(sample);
+            }
+        }
+        Ok(Self::new(
+            time,
+            data.into_iter()
+                .filter_map(|(name, io)| Loads::from_name(name, io))
+                .collect(),
+        ))
+    }
+    /// Reads the wind loads from a NumPy archive
+    ///
+    /// The archive contains a `time` array and one 2D array per load group named after the load group, e.g. `OSS_Truss_6F`.
+    /// The 2D arrays have one row per time sample and must be either `float64` or `float32`.
+    pub fn from_npz<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = File::open(path)?;
+        let mut archive = zip::ZipArchive::new(BufReader::new(f))?;
+        let mut time = None;
+        let mut loads = vec![];
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let name = file.name().trim_end_matches(".npy").to_owned();
+            if name != "time" && !Loads::names().contains(&name.as_str()) {
+                log::warn!("Skipping unknown wind loads array: {}", name);
+                continue;
+            }
+            let mut buffer = vec![];
+            file.read_to_end(&mut buffer)?;
+            let (shape, values) = read_npy(&buffer).map_err(|e| {
+                DOSError::Component(WindLoadsError::Format(format!("{}: {}", name, e)))
+            })?;
+            if name == "time" {
+                time = Some(values);
+            } else {
+                let n_element = shape.get(1).cloned().unwrap_or(1).max(1);
+                let io = values.chunks(n_element).map(|x| x.to_vec()).collect();
+                loads.extend(Loads::from_name(&name, io));
+            }
+        }
+        let time =
+            time.ok_or_else(|| DOSError::Component(WindLoadsError::Missing("time".to_owned())))?;
+        Ok(Self::new(time, loads))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("dos-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn csv_wind_loads() {
+        let path = temp_file("loads.csv");
+        std::fs::write(
+            &path,
+            "OSS_GIR_6F_1,time,OSS_GIR_6F_0\n2,0.0,1\n4,0.5,3\n6,1.0,5\n",
+        )
+        .unwrap();
+        let wind_loads = WindLoads::from_file(&path).unwrap();
+        assert_eq!(wind_loads.time, vec![0., 0.5, 1.]);
+        assert_eq!(
+            wind_loads.loads[0].as_ref().unwrap().as_slice(),
+            &[vec![1., 2.], vec![3., 4.], vec![5., 6.]]
+        );
+        for header in &[
+            "time,OSS_GIR_6F_0,OSS_GIR_6F_2",
+            "time,OSS_GIR_6F_0,OSS_GIR_6F_0",
+            "time,OSS_GIR_6F_1",
+            "time,OSS_GIR_6F_0,OSS_GIR",
+            "OSS_GIR_6F_0,OSS_GIR_6F_1",
+        ] {
+            std::fs::write(&path, format!("{}\n0,1,2\n", header)).unwrap();
+            assert!(WindLoads::from_csv(&path).is_err(), "{}", header);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn file_format_dispatch() {
+        let npy = |shape: &str, values: &[f64]| {
+            let header = format!(
+                "{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}",
+                shape
+            );
+            let mut buffer = b"\x93NUMPY\x01\x00".to_vec();
+            buffer.extend_from_slice(&(header.len() as u16).to_le_bytes());
+            buffer.extend_from_slice(header.as_bytes());
+            values
+                .iter()
+                .for_each(|x| buffer.extend_from_slice(&x.to_le_bytes()));
+            buffer
+        };
+        let path = temp_file("loads.npz");
+        {
+            use std::io::Write;
+            let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            zip.start_file("time.npy", options).unwrap();
+            zip.write_all(&npy("(2,)", &[0., 0.5])).unwrap();
+            zip.start_file("OSS_Truss_6F.npy", options).unwrap();
+            zip.write_all(&npy("(2, 3)", &[1., 2., 3., 4., 5., 6.]))
+                .unwrap();
+            zip.finish().unwrap();
+        }
+        let wind_loads = WindLoads::from_file(&path).unwrap();
+        assert_eq!(wind_loads.time, vec![0., 0.5]);
+        assert_eq!(
+            wind_loads.loads[0].as_ref().unwrap().as_slice(),
+            &[vec![1., 2., 3.], vec![4., 5., 6.]]
+        );
+        std::fs::remove_file(path).unwrap();
+        assert!(WindLoads::from_file(temp_file("loads.csv")).is_err());
+        match WindLoads::from_file("wind_loads.txt") {
+            Err(DOSError::Component(WindLoadsError::Format(_))) => (),
+            _ => panic!("expected a format error"),
+        }
+    }
+}
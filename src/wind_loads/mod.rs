@@ -0,0 +1,773 @@
+//! This module is used to create the structure that applied wind forces and moments on the telescope FEM
+//!
+//! There are 7 wind load sources applied to the following elements of the telescope structure:
+//!  - the C-Rings
+//!  - the GIR
+//!  - the M1 cells
+//!  - the M1 segments
+//!  - the trusses
+//!  - the M2 segments
+//!  - the top-end
+//!
+//! The wind loads are either read at once from a pickle file with [`WindLoads`] or streamed from a chunked data store with [`ChunkedWindLoads`](crate::wind_loads::chunked::ChunkedWindLoads).
+//! When no CFD case is available, stochastic wind loads are generated with [`SyntheticWindLoads`](crate::wind_loads::synthetic::SyntheticWindLoads).
+//! The wind loads are checked before a simulation with [`WindLoads::report`].
+//! Several records are combined on the same FEM inputs with [`WindLoads::superpose`] or [`WindLoading::superpose`].
+
+use super::{
+    io::{jar, Tags},
+    DOSError, IOTags, DOS, IO,
+};
+use serde;
+use serde::{Deserialize, Serialize};
+use serde_pickle as pkl;
+use std::fs::File;
+use std::io::BufReader;
+use std::ops::Range;
+use std::path::Path;
+
+pub mod chunked;
+pub mod filters;
+pub mod formats;
+pub mod report;
+pub mod similarity;
+pub mod superposition;
+pub mod synthetic;
+#[doc(inline)]
+pub use filters::Interpolation;
+#[doc(inline)]
+pub use similarity::WindConditions;
+
+#[derive(Clone, Debug)]
+pub enum WindLoadsError {
+    Len,
+    Empty,
+    FileNotFound,
+    PickleRead,
+    Outputs,
+    Missing(String),
+    Format(String),
+    Mismatch(String),
+    Psd(String),
+    Range(String),
+}
+
+type Result<T> = std::result::Result<T, DOSError<WindLoadsError>>;
+type Outputs = Option<std::vec::IntoIter<Vec<f64>>>;
+
+/// Returns the indices `[min,max[` of the time samples within the time range [`t_min`,`t_max`[
+fn range_indices(time: &[f64], t_min: f64, t_max: f64) -> Result<(usize, usize)> {
+    if t_min >= t_max {
+        return Err(DOSError::Component(WindLoadsError::Range(format!(
+            "t_min ({}) must be less than t_max ({})",
+            t_min, t_max
+        ))));
+    }
+    let min_index = time.iter().position(|t| *t >= t_min).unwrap_or(time.len());
+    let max_index = time.iter().position(|t| *t >= t_max).unwrap_or(time.len());
+    if min_index == max_index {
+        return Err(DOSError::Component(WindLoadsError::Range(format!(
+            "no time sample within [{},{}[",
+            t_min, t_max
+        ))));
+    }
+    Ok((min_index, max_index))
+}
+
+macro_rules! loads {
+    ($($name:expr, $variant:ident),+) => {
+        /// Wind loads forces and moments
+        ///
+        /// A time vector containing vectors of forces and moments
+        #[derive(Deserialize, Debug,Clone)]
+        pub enum Loads {
+            $(#[serde(rename = $name)]
+              $variant(Vec<Vec<f64>>)),+
+        }
+        impl Loads {
+            /// Returns the number of samples in the time series
+            pub fn len(&self) -> usize {
+                match self {
+                    $(Loads::$variant(io) => io.len()),+
+                }
+            }
+            /// Returns true if the time series is empty
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+            /// Return the loads
+            pub fn io(self) -> Vec<Vec<f64>> {
+                match self {
+                    $(Loads::$variant(io) => io),+
+                }
+            }
+            /// Returns a reference to the loads
+            pub fn as_slice(&self) -> &[Vec<f64>] {
+                match self {
+                    $(Loads::$variant(io) => io.as_slice()),+
+                }
+            }
+            /// Returns the name of the loads in the CFD data file
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Loads::$variant(_) => $name),+
+                }
+            }
+            /// Returns the `IO` tag of the loads
+            pub fn tag(&self) -> Tags {
+                match self {
+                    $(Loads::$variant(_) => jar::$variant::new()),+
+                }
+            }
+            /// Creates the loads from the name of the loads in the CFD data file
+            pub fn from_name(name: &str, io: Vec<Vec<f64>>) -> Option<Self> {
+                match name {
+                    $($name => Some(Loads::$variant(io)),)+
+                    _ => None,
+                }
+            }
+            /// Returns the names of all the loads
+            pub fn names() -> Vec<&'static str> {
+                vec![$($name),+]
+            }
+            /// Returns the name of the loads matching an `IO` tag
+            pub fn name_of(tag: &Tags) -> Option<&'static str> {
+                match tag {
+                    $(IO::$variant{..} => Some($name),)+
+                    _ => None,
+                }
+            }
+            /// Low-pass filters and decimates the loads (see [`filters`](crate::wind_loads::filters))
+            pub fn decimate(&mut self, decimation_rate: usize) {
+                match self {
+                    $(Loads::$variant(io) => {
+                        *io = filters::decimate(io, decimation_rate);
+                    }),+
+                }
+            }
+            /// Multiplies the loads by `factor`
+            pub fn scale(&mut self, factor: f64) {
+                match self {
+                    $(Loads::$variant(io) => {
+                        io.iter_mut().flat_map(|x| x.iter_mut()).for_each(|x| *x *= factor);
+                    }),+
+                }
+            }
+            /// Resamples the loads from the time samples `time` to `new_time`
+            pub fn resample(&mut self, time: &[f64], new_time: &[f64], interpolation: Interpolation) {
+                match self {
+                    $(Loads::$variant(io) => {
+                        *io = filters::resample(time, io, new_time, interpolation);
+                    }),+
+                }
+            }
+            pub fn range(&mut self, min_index: usize, max_index: usize) {
+                match self {
+                    $(Loads::$variant(io) => {
+                        let ranged: Vec<_> = io.iter()
+                            .skip(min_index)
+                            .take(max_index-min_index)
+                            .cloned()
+                            .collect();
+                        *io = ranged;
+                    }),+
+                }
+            }
+        }
+    };
+}
+loads!(
+    "OSS_TopEnd_6F",
+    OSSTopEnd6F,
+    "OSS_Truss_6F",
+    OSSTruss6F,
+    "OSS_GIR_6F",
+    OSSGIR6F,
+    "OSS_CRING_6F",
+    OSSCRING6F,
+    "OSS_Cell_lcl_6F",
+    OSSCellLcl6F,
+    "OSS_M1_lcl_6F",
+    OSSM1Lcl6F,
+    "MC_M2_lcl_force_6F",
+    MCM2Lcl6F
+);
+
+/// Routing of a load group to a FEM input
+///
+/// A list of routes is given to [`WindLoads::routes`] or read from a pickle file with [`WindLoads::routes_from_pickle`].
+/// In Python, a route is the dictionary `{'group': 'OSS_TopEnd_6F', 'input': 'MCM2TE6F', 'components': (0, 6)}`
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Route {
+    /// Name of the load group in the CFD data file
+    pub group: String,
+    /// Name of the FEM input `IO` variant
+    pub input: String,
+    /// Range `[start,end[` of the load group components applied to the FEM input, all the components if `None`
+    #[serde(default)]
+    pub components: Option<(usize, usize)>,
+}
+
+/// Wind loads builder
+///
+/// This structure is used to read the forces and moments time series from a data file and to create the [`WindLoading`] structure
+#[derive(Deserialize)]
+pub struct WindLoads {
+    /// forces and moments time series
+    #[serde(rename = "outputs")]
+    pub loads: Vec<Option<Loads>>,
+    /// time vector
+    pub time: Vec<f64>,
+    #[serde(skip)]
+    n_sample: Option<usize>,
+    #[serde(skip)]
+    tagged_loads: Vec<IO<std::vec::IntoIter<Vec<f64>>>>,
+    #[serde(skip)]
+    ramp_in: Option<f64>,
+    #[serde(skip)]
+    offset: Option<f64>,
+    #[serde(skip)]
+    crossfade: Option<f64>,
+    #[serde(skip)]
+    resettable: bool,
+}
+
+impl WindLoads {
+    /// Creates the wind loads from the time vector and the forces and moments time series
+    pub fn new(time: Vec<f64>, loads: Vec<Loads>) -> Self {
+        Self {
+            loads: loads.into_iter().map(Some).collect(),
+            time,
+            n_sample: None,
+            tagged_loads: vec![],
+            ramp_in: None,
+            offset: None,
+            crossfade: None,
+            resettable: false,
+        }
+    }
+    /// Reads the wind loads from a pickle file
+    pub fn from_pickle<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = File::open(path)?;
+        let r = BufReader::with_capacity(1_000_000_000, f);
+        let v: serde_pickle::Value = serde_pickle::from_reader(r)?;
+        Ok(pkl::from_value(v)?)
+    }
+    /// Returns the number of samples in the time series
+    fn len(&self) -> Result<usize> {
+        self.loads
+            .iter()
+            .find_map(|x| x.as_ref().map(|x| x.len()))
+            .ok_or(DOSError::Component(WindLoadsError::Len))
+    }
+    /// Selects the loads and the time samples within the time range [`t_min`,`t_max`[
+    ///
+    /// Returns an error if `t_min` is not less than `t_max` or if the time range does not overlap the record
+    pub fn range(mut self, t_min: f64, t_max: f64) -> Result<Self> {
+        let (min_index, max_index) = range_indices(&self.time, t_min, t_max)?;
+        self.loads
+            .iter_mut()
+            .filter_map(|x| x.as_mut())
+            .for_each(|x| {
+                x.range(min_index, max_index);
+            });
+        self.time = self.time[min_index..max_index].to_vec();
+        Ok(self)
+    }
+    /// Low-pass filters and decimates the loads and decimates the time vector accordingly
+    pub fn decimate(mut self, decimation_rate: usize) -> Self {
+        self.loads
+            .iter_mut()
+            .filter_map(|x| x.as_mut())
+            .for_each(|x| {
+                x.decimate(decimation_rate);
+            });
+        self.time = self
+            .time
+            .into_iter()
+            .step_by(decimation_rate.max(1))
+            .collect();
+        self
+    }
+    /// Resamples the loads and the time vector at `sampling_rate`
+    ///
+    /// The new time vector starts at the same time than the original one and ends on or before the last original time sample
+    pub fn resample(mut self, sampling_rate: f64, interpolation: Interpolation) -> Self {
+        let (t_start, t_end) = match (self.time.first(), self.time.last()) {
+            (Some(t_start), Some(t_end)) => (*t_start, *t_end),
+            _ => return self,
+        };
+        let n = ((t_end - t_start) * sampling_rate + 1e-9).floor() as usize + 1;
+        let new_time: Vec<f64> = (0..n)
+            .map(|k| t_start + k as f64 / sampling_rate)
+            .collect();
+        let time = &self.time;
+        self.loads
+            .iter_mut()
+            .filter_map(|x| x.as_mut())
+            .for_each(|x| {
+                x.resample(time, &new_time, interpolation);
+            });
+        self.time = new_time;
+        self
+    }
+    /// Returns the sampling rate of the time vector
+    fn sampling_rate(&self) -> Result<f64> {
+        match self.time.as_slice() {
+            [t0, t1, ..] if t1 > t0 => Ok((t1 - t0).recip()),
+            _ => Err(DOSError::Component(WindLoadsError::Len)),
+        }
+    }
+    fn tagged_load(&self, io: &Tags) -> Result<Outputs> {
+        self.loads
+            .iter()
+            .find_map(|x| x.as_ref().and_then(|x| io.data(x)))
+            .map_or(
+                Err(DOSError::Component(WindLoadsError::Missing(
+                    Loads::name_of(io).map_or(format!("{:?}", io), |name| name.to_owned()),
+                ))),
+                |x| Ok(Some(x)),
+            )
+    }
+    /// Set the number of time sample
+    ///
+    /// If the loads are [`looping`](WindLoads::looping), the number of time sample can be greater than the number of samples in the record,
+    /// otherwise [`build`](WindLoads::build) returns an error
+    pub fn n_sample(self, n_sample: usize) -> Result<Self> {
+        if n_sample == 0 {
+            return Err(DOSError::Component(WindLoadsError::Range(
+                "n_sample must be greater than 0".to_owned(),
+            )));
+        }
+        Ok(Self {
+            n_sample: Some(n_sample),
+            ..self
+        })
+    }
+    /// Ramps in the loads with a raised cosine over `duration` seconds
+    pub fn ramp_in(self, duration: f64) -> Self {
+        Self {
+            ramp_in: Some(duration),
+            ..self
+        }
+    }
+    /// Starts the loads `t_offset` seconds after the beginning of the record
+    pub fn offset(self, t_offset: f64) -> Self {
+        Self {
+            offset: Some(t_offset),
+            ..self
+        }
+    }
+    /// Loops over the record until [`n_sample`](WindLoads::n_sample) samples have been output
+    ///
+    /// The end of the record is cross-faded into the beginning of the record over `crossfade` seconds, so the looped loads are continuous.
+    pub fn looping(self, crossfade: f64) -> Self {
+        Self {
+            crossfade: Some(crossfade),
+            ..self
+        }
+    }
+    /// Keeps a copy of the loads in the wind loading so it can be [`reset`](crate::DOS::reset)
+    ///
+    /// The copy doubles the memory used by the wind loading, a [`looping`](WindLoads::looping) wind loading already keeps a copy
+    pub fn resettable(self) -> Self {
+        Self {
+            resettable: true,
+            ..self
+        }
+    }
+    /// Applies the loads of the load group `group` to the FEM input `input`
+    ///
+    /// If loads have already been applied to `input`, the loads of `group` are added to them
+    pub fn map(self, group: Tags, input: Tags) -> Result<Self> {
+        self.route(group, None, input)
+    }
+    /// Applies the components `components` of the loads of the load group `group` to the FEM input `input`
+    ///
+    /// A load group is split across several FEM inputs by mapping different components to different inputs.
+    /// If loads have already been applied to `input`, the loads of `group` are added to them
+    pub fn map_components(self, group: Tags, components: Range<usize>, input: Tags) -> Result<Self> {
+        self.route(group, Some(components), input)
+    }
+    fn route(
+        mut self,
+        group: Tags,
+        components: Option<Range<usize>>,
+        input: Tags,
+    ) -> Result<Self> {
+        let mut loads: Vec<Vec<f64>> = self
+            .tagged_load(&group)?
+            .map_or(vec![], |x| x.collect());
+        if let Some(components) = components {
+            let n = loads.first().map_or(0, |x| x.len());
+            if components.end > n || components.start >= components.end {
+                return Err(DOSError::Component(WindLoadsError::Mismatch(format!(
+                    "components {:?} out of {:?} {} components",
+                    components, group, n
+                ))));
+            }
+            loads
+                .iter_mut()
+                .for_each(|x| *x = x[components.clone()].to_vec());
+        }
+        match self.tagged_loads.iter_mut().find(|x| **x == input) {
+            Some(tagged_load) => {
+                let previous: Vec<Vec<f64>> =
+                    Option::<std::vec::IntoIter<Vec<f64>>>::from(&*tagged_load)
+                        .map_or(vec![], |x| x.collect());
+                if previous.first().map(|x| x.len()) != loads.first().map(|x| x.len()) {
+                    return Err(DOSError::Component(WindLoadsError::Mismatch(format!(
+                        "{:?} and {:?} have different sizes",
+                        group, input
+                    ))));
+                }
+                let sum: Vec<Vec<f64>> = previous
+                    .into_iter()
+                    .zip(loads)
+                    .map(|(x, y)| x.iter().zip(y).map(|(x, y)| x + y).collect())
+                    .collect();
+                *tagged_load = (&input, Some(sum.into_iter())).into();
+            }
+            None => self
+                .tagged_loads
+                .push((&input, Some(loads.into_iter())).into()),
+        }
+        Ok(self)
+    }
+    /// Applies the loads according to a list of [`Route`]s
+    pub fn routes(self, routes: &[Route]) -> Result<Self> {
+        routes.iter().try_fold(self, |this, route| {
+            let group = Loads::from_name(&route.group, vec![])
+                .map(|x| x.tag())
+                .ok_or_else(|| DOSError::Component(WindLoadsError::Missing(route.group.clone())))?;
+            let input: Tags = route.input.parse().map_err(|_| {
+                DOSError::Component(WindLoadsError::Format(format!(
+                    "unknown FEM input: {}",
+                    route.input
+                )))
+            })?;
+            this.route(
+                group,
+                route.components.map(|(start, end)| start..end),
+                input,
+            )
+        })
+    }
+    /// Applies the loads according to a list of [`Route`]s read from a pickle file
+    pub fn routes_from_pickle<P: AsRef<Path>>(self, path: P) -> Result<Self> {
+        let f = File::open(path)?;
+        let routes: Vec<Route> = pkl::from_reader(BufReader::new(f))?;
+        self.routes(&routes)
+    }
+    /// Selects loads on the truss
+    pub fn truss(self) -> Result<Self> {
+        self.map(jar::OSSTruss6F::new(), jar::OSSTruss6F::new())
+    }
+    /// Selects loads on the top-end
+    pub fn topend(self) -> Result<Self> {
+        self.map(jar::OSSTopEnd6F::new(), jar::OSSTopEnd6F::new())
+    }
+    /// Selects loads on the top-end and applies them to the ASM top-end input `MCM2TE6F`
+    pub fn m2_asm_topend(self) -> Result<Self> {
+        self.map(jar::OSSTopEnd6F::new(), jar::MCM2TE6F::new())
+    }
+    /// Selects loads on the C-ring
+    pub fn cring(self) -> Result<Self> {
+        self.map(jar::OSSCRING6F::new(), jar::OSSCRING6F::new())
+    }
+    /// Selects loads on the GIR
+    pub fn gir(self) -> Result<Self> {
+        self.map(jar::OSSGIR6F::new(), jar::OSSGIR6F::new())
+    }
+    /// Selects loads on the M1 cells
+    pub fn m1_cell(self) -> Result<Self> {
+        self.map(jar::OSSCellLcl6F::new(), jar::OSSCellLcl6F::new())
+    }
+    /// Selects loads on the M1 segments
+    pub fn m1_segments(self) -> Result<Self> {
+        self.map(jar::OSSM1Lcl6F::new(), jar::OSSM1Lcl6F::new())
+    }
+    /// Selects loads on the M2 segments
+    pub fn m2_segments(self) -> Result<Self> {
+        self.map(jar::MCM2Lcl6F::new(), jar::MCM2Lcl6F::new())
+    }
+    /// Selects loads on the M2 segments and applies them to the ASM reference bodies input `MCM2RB6F`
+    pub fn m2_asm_reference_bodies(self) -> Result<Self> {
+        self.map(jar::MCM2Lcl6F::new(), jar::MCM2RB6F::new())
+    }
+    /// Selects all loads
+    pub fn select_all(self) -> Result<Self> {
+        self.topend()?
+            .m2_segments()?
+            .truss()?
+            .m1_segments()?
+            .m1_cell()?
+            .gir()?
+            .cring()
+    }
+    /// Selects all loads in the ASM configuration
+    pub fn select_all_with_asm(self) -> Result<Self> {
+        self.m2_asm_topend()?
+            .m2_asm_reference_bodies()?
+            .truss()?
+            .m1_segments()?
+            .m1_cell()?
+            .gir()?
+            .cring()
+    }
+    /// Builds a wind loading source object
+    pub fn build(self) -> Result<WindLoading> {
+        let n = self.len()?;
+        let n_sample = self.n_sample.unwrap_or(n);
+        if n_sample > n && self.crossfade.is_none() {
+            return Err(DOSError::Component(WindLoadsError::Range(format!(
+                "n_sample ({}) cannot be greater than the number of sample ({}) if the loads are not looping",
+                n_sample, n
+            ))));
+        }
+        let (ramp_in, offset, crossfade) = match (self.ramp_in, self.offset, self.crossfade) {
+            (None, None, None) => (0, 0, None),
+            (ramp_in, offset, crossfade) => {
+                let sampling_rate = self.sampling_rate()?;
+                let to_samples = |t: f64| (t * sampling_rate).round() as usize;
+                (
+                    ramp_in.map_or(0, to_samples),
+                    offset.map_or(0, to_samples),
+                    crossfade.map(to_samples),
+                )
+            }
+        };
+        let mut loads = vec![];
+        let mut record = vec![];
+        for tagged_load in self.tagged_loads {
+            let tag: Tags = (&tagged_load).into();
+            let mut data: Vec<Vec<f64>> = Option::<std::vec::IntoIter<Vec<f64>>>::from(tagged_load)
+                .map_or(vec![], |x| x.collect());
+            if let Some(n_crossfade) = crossfade {
+                filters::crossfade(&mut data, n_crossfade);
+            }
+            if crossfade.is_some() || self.resettable {
+                record.push((&tag, Some(data.clone().into_iter())).into());
+            }
+            loads.push(WindLoading::start(&tag, data, offset, n_sample, crossfade.is_some()));
+        }
+        Ok(WindLoading {
+            time: self.time,
+            n_sample,
+            loads,
+            record: if crossfade.is_some() || self.resettable {
+                Some(record)
+            } else {
+                None
+            },
+            looping: crossfade.is_some(),
+            offset,
+            ramp_in,
+            step: 0,
+        })
+    }
+}
+
+/// Wind loading sources
+///
+/// This structure contains the time series of wind forces and moments.
+/// The time series implement the [`Iterator`] trait and the [`outputs`](crate::wind_loads::WindLoading::outputs) method step through the iterator
+#[derive(Default)]
+pub struct WindLoading {
+    pub loads: Vec<IO<std::vec::IntoIter<Vec<f64>>>>,
+    pub n_sample: usize,
+    time: Vec<f64>,
+    record: Option<Vec<IO<std::vec::IntoIter<Vec<f64>>>>>,
+    looping: bool,
+    offset: usize,
+    ramp_in: usize,
+    step: usize,
+}
+impl WindLoading {
+    /// Returns the loads starting at `offset`, truncated to `n_sample` if the loads are not looping
+    fn start(
+        tag: &Tags,
+        mut data: Vec<Vec<f64>>,
+        offset: usize,
+        n_sample: usize,
+        looping: bool,
+    ) -> IO<std::vec::IntoIter<Vec<f64>>> {
+        let mut data = data.split_off(offset.min(data.len()));
+        if !looping {
+            data.truncate(n_sample);
+        }
+        (tag, Some(data.into_iter())).into()
+    }
+    /// Returns the ramp-in weight of the current sample
+    fn ramp_in_weight(&self) -> f64 {
+        if self.step < self.ramp_in {
+            0.5 * (1. - (std::f64::consts::PI * self.step as f64 / self.ramp_in as f64).cos())
+        } else {
+            1f64
+        }
+    }
+}
+
+/// Wind loading interface
+impl IOTags for WindLoading {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        self.loads.iter().map(|x| x.into()).collect()
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        vec![]
+    }
+}
+impl DOS for WindLoading {
+    fn inputs(
+        &mut self,
+        _: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        Err("WindLoading takes no inputs".into())
+    }
+    /// Restarts the loads from the copy kept by a [`resettable`](WindLoads::resettable) or [`looping`](WindLoads::looping) wind loading
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        let record = self
+            .record
+            .as_ref()
+            .ok_or("WindLoading cannot be reset, see WindLoads::resettable")?;
+        self.loads = record
+            .iter()
+            .map(|x| {
+                let data: Vec<Vec<f64>> =
+                    Option::<std::vec::IntoIter<Vec<f64>>>::from(x).map_or(vec![], |x| x.collect());
+                WindLoading::start(&x.into(), data, self.offset, self.n_sample, self.looping)
+            })
+            .collect();
+        self.step = 0;
+        Ok(self)
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        if self.looping && self.step >= self.n_sample {
+            return None;
+        }
+        let weight = self.ramp_in_weight();
+        let record = if self.looping { &self.record } else { &None };
+        let outputs = self
+            .loads
+            .iter_mut()
+            .enumerate()
+            .map(|(k, x)| -> Option<IO<Vec<f64>>> {
+                let mut y: Option<IO<Vec<f64>>> = x.into();
+                if let (None, Some(record)) = (&y, record) {
+                    *x = record[k].clone();
+                    y = x.into();
+                }
+                y.map(|y| y.map(|y| y.into_iter().map(|y| y * weight).collect()))
+            })
+            .collect();
+        self.step += 1;
+        outputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wind_loads_map() {
+        let time: Vec<f64> = (0..4).map(|k| k as f64).collect();
+        let wind_loads = WindLoads::new(
+            time,
+            vec![
+                Loads::OSSTopEnd6F(vec![vec![1f64; 6]; 4]),
+                Loads::MCM2Lcl6F(vec![vec![2f64; 42]; 4]),
+            ],
+        );
+        let mut wind_loading = wind_loads
+            .map(jar::OSSTopEnd6F::new(), jar::MCM2TE6F::new())
+            .unwrap()
+            .map_components(jar::MCM2Lcl6F::new(), 0..6, jar::MCM2TE6F::new())
+            .unwrap()
+            .map_components(jar::MCM2Lcl6F::new(), 6..42, jar::MCM2RB6F::new())
+            .unwrap()
+            .build()
+            .unwrap();
+        let outputs = wind_loading.outputs().unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(
+            Option::<Vec<f64>>::from(&outputs[jar::MCM2TE6F::new()]).unwrap(),
+            vec![3f64; 6]
+        );
+        assert_eq!(
+            Option::<Vec<f64>>::from(&outputs[jar::MCM2RB6F::new()])
+                .unwrap()
+                .len(),
+            36
+        );
+    }
+
+    #[test]
+    fn wind_loads_range() {
+        let wind_loads = || {
+            WindLoads::new(
+                (0..10).map(|k| k as f64).collect(),
+                vec![Loads::OSSTruss6F(
+                    (0..10).map(|k| vec![k as f64; 6]).collect(),
+                )],
+            )
+        };
+        let ranged = wind_loads().range(2.5, 5.0).unwrap();
+        assert_eq!(ranged.time, vec![3., 4.]);
+        assert_eq!(ranged.loads[0].as_ref().unwrap().as_slice()[0], vec![3f64; 6]);
+        assert_eq!(wind_loads().range(-1.0, 20.0).unwrap().time.len(), 10);
+        assert!(wind_loads().range(5.0, 2.0).is_err());
+        assert!(wind_loads().range(10.0, 20.0).is_err());
+        assert!(wind_loads().range(2.2, 2.8).is_err());
+    }
+
+    #[test]
+    fn wind_loads_ramp_in_looping() {
+        let time: Vec<f64> = (0..10).map(|k| k as f64).collect();
+        let mut wind_loading = WindLoads::new(
+            time,
+            vec![Loads::OSSTruss6F(vec![vec![1f64; 6]; 10])],
+        )
+        .ramp_in(4.0)
+        .n_sample(25)
+        .unwrap()
+        .looping(2.0)
+        .truss()
+        .unwrap()
+        .build()
+        .unwrap();
+        let mut y = vec![];
+        while let Some(outputs) = wind_loading.outputs() {
+            y.push(Option::<Vec<f64>>::from(&outputs[0]).unwrap()[0]);
+        }
+        assert_eq!(y.len(), 25);
+        assert_eq!(y[0], 0f64);
+        assert!(y[1] > 0f64 && y[1] < y[2] && y[2] < y[3] && y[3] < 1f64);
+        assert!(y[4..].iter().all(|y| (y - 1.).abs() < 1e-12));
+    }
+
+    #[test]
+    fn wind_loads_n_sample() {
+        let wind_loads = || {
+            WindLoads::new(
+                (0..10).map(|k| k as f64).collect(),
+                vec![Loads::OSSTruss6F(vec![vec![1f64; 6]; 10])],
+            )
+        };
+        assert!(wind_loads().n_sample(0).is_err());
+        assert!(wind_loads()
+            .n_sample(25)
+            .and_then(|x| x.truss())
+            .and_then(|x| x.build())
+            .is_err());
+        assert_eq!(
+            wind_loads()
+                .n_sample(5)
+                .and_then(|x| x.truss())
+                .and_then(|x| x.build())
+                .unwrap()
+                .n_sample,
+            5
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! This module is used to check the wind loads before running a simulation
+//!
+//! [`WindLoads::report`] computes the mean, the RMS, the peak and the power spectral density (PSD) of each force and moment component of each load group
+//! and of the total force, sum of the forces of all the load groups.
+//! The PSDs are estimated with Welch method: Hann window and 50% overlap.
+//!
+//! The report also lists the problems found in the data: non-finite values, load groups shorter or longer than the time vector and non-uniform time steps.
+//! It is written either as a text file, without the PSDs, or as a pickle file (see [`WindLoadsReport::to_file`]).
+//!
+//! # Example
+//! ```no_run
+//! use dos::wind_loads::WindLoads;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let report = WindLoads::from_pickle("b2019_0z_0az_os_7ms.pkl")?.report(4096)?;
+//!     report.to_file("b2019_0z_0az_os_7ms_report.txt")?;
+//!     if !report.is_valid() {
+//!         println!("{}", report);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use super::{synthetic::inverse_fft, Result, WindLoads, WindLoadsError};
+use crate::DOSError;
+use num_complex::Complex;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_pickle as pkl;
+use std::f64::consts::PI;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Statistics of a force or moment component
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Statistics {
+    pub mean: f64,
+    pub rms: f64,
+    /// Maximum absolute value
+    pub peak: f64,
+    /// One-sided PSD in unit<sup>2</sup>/Hz
+    pub psd: Vec<f64>,
+}
+impl Statistics {
+    /// Computes the statistics of the time series `x` sampled at `sampling_rate` with PSD segments of `n_fft` samples
+    ///
+    /// `n_fft` is rounded down to a power of 2 no larger than the length of `x`
+    pub fn new(x: &[f64], sampling_rate: f64, n_fft: usize) -> Result<Self> {
+        Ok(Self::compute(x, sampling_rate, segment_size(n_fft, x.len())?))
+    }
+    // `n_fft` must be a power of 2 no larger than the length of `x`
+    fn compute(x: &[f64], sampling_rate: f64, n_fft: usize) -> Self {
+        let n = x.len().max(1) as f64;
+        Self {
+            mean: x.iter().sum::<f64>() / n,
+            rms: (x.iter().map(|x| x * x).sum::<f64>() / n).sqrt(),
+            peak: x.iter().fold(0f64, |p, x| p.max(x.abs())),
+            psd: welch(x, sampling_rate, n_fft),
+        }
+    }
+}
+
+/// Report of a load group
+#[derive(Serialize, Debug, Clone)]
+pub struct GroupReport {
+    /// Name of the loads in the CFD data file
+    pub name: String,
+    pub n_sample: usize,
+    /// Statistics of each force and moment component
+    pub components: Vec<Statistics>,
+}
+
+/// Wind loads report
+#[derive(Serialize, Debug, Clone)]
+pub struct WindLoadsReport {
+    /// Sampling rate in Hz
+    pub sampling_rate: f64,
+    /// Duration in s
+    pub duration: f64,
+    /// Frequency vector of the PSDs in Hz
+    pub frequency: Vec<f64>,
+    pub groups: Vec<GroupReport>,
+    /// Statistics of the total force components (x, y, z)
+    pub total_force: Vec<Statistics>,
+    /// Problems found in the data
+    pub issues: Vec<String>,
+}
+
+/// Returns the largest power of 2 no larger than `n_fft` and `n_sample`
+fn segment_size(n_fft: usize, n_sample: usize) -> Result<usize> {
+    match n_fft.min(n_sample) {
+        n if n < 2 => Err(DOSError::Component(WindLoadsError::Psd(format!(
+            "no PSD segment of {} samples fits in {} samples",
+            n_fft, n_sample
+        )))),
+        n => Ok(1 << (usize::BITS - 1 - n.leading_zeros())),
+    }
+}
+
+/// One-sided PSD with Welch method with segments of `n_fft` samples, see [`segment_size`]
+fn welch(x: &[f64], sampling_rate: f64, n_fft: usize) -> Vec<f64> {
+    let window: Vec<f64> = (0..n_fft)
+        .map(|k| 0.5 * (1. - (2. * PI * k as f64 / n_fft as f64).cos()))
+        .collect();
+    let norm = sampling_rate * window.iter().map(|w| w * w).sum::<f64>();
+    let mut psd = vec![0f64; n_fft / 2 + 1];
+    let mut n_segment = 0;
+    let mut start = 0;
+    while start + n_fft <= x.len() {
+        // the inverse FFT of a real sequence has the same magnitude than its FFT
+        let mut y: Vec<Complex<f64>> = x[start..start + n_fft]
+            .iter()
+            .zip(window.iter())
+            .map(|(x, w)| Complex::new(x * w, 0f64))
+            .collect();
+        inverse_fft(&mut y);
+        psd.iter_mut()
+            .zip(y.iter())
+            .for_each(|(p, y)| *p += y.norm_sqr());
+        n_segment += 1;
+        start += n_fft / 2;
+    }
+    let n = psd.len();
+    psd.iter_mut().enumerate().for_each(|(k, p)| {
+        *p /= norm * n_segment as f64;
+        if k > 0 && k < n - 1 {
+            *p *= 2.;
+        }
+    });
+    psd
+}
+
+impl WindLoads {
+    /// Computes the wind loads report with PSD segments of `n_fft` samples
+    ///
+    /// `n_fft` is rounded down to a power of 2 no larger than the shortest time series
+    pub fn report(&self, n_fft: usize) -> Result<WindLoadsReport> {
+        let sampling_rate = self.sampling_rate()?;
+        let n_time = self.time.len();
+        let n_fft = segment_size(
+            n_fft,
+            self.loads
+                .iter()
+                .filter_map(|x| x.as_ref().map(|x| x.len()))
+                .fold(n_time, usize::min),
+        )?;
+        let mut issues = vec![];
+        let dt = sampling_rate.recip();
+        if let Some(k) = self
+            .time
+            .windows(2)
+            .position(|t| ((t[1] - t[0]) - dt).abs() > 1e-3 * dt)
+        {
+            issues.push(format!("non-uniform time step at t={}s", self.time[k]));
+        }
+        let mut total_force: Vec<Vec<f64>> = vec![vec![0f64; n_time]; 3];
+        let mut groups = vec![];
+        for loads in self.loads.iter().filter_map(|x| x.as_ref()) {
+            let data = loads.as_slice();
+            let name = loads.name();
+            if data.len() != n_time {
+                issues.push(format!(
+                    "{}: {} samples for {} time samples",
+                    name,
+                    data.len(),
+                    n_time
+                ));
+            }
+            let n_element = data.first().map_or(0, |x| x.len());
+            if data.iter().any(|x| x.len() != n_element) {
+                return Err(DOSError::Component(WindLoadsError::Mismatch(format!(
+                    "{}: samples of different sizes",
+                    name
+                ))));
+            }
+            let n_nan = data
+                .iter()
+                .flat_map(|x| x.iter())
+                .filter(|x| !x.is_finite())
+                .count();
+            if n_nan > 0 {
+                issues.push(format!("{}: {} non-finite values", name, n_nan));
+            }
+            for (sample, total) in data.iter().zip(0..n_time) {
+                sample
+                    .chunks(6)
+                    .filter(|x| x.len() == 6)
+                    .for_each(|x| (0..3).for_each(|i| total_force[i][total] += x[i]));
+            }
+            let components = (0..n_element)
+                .into_par_iter()
+                .map(|j| {
+                    let x: Vec<f64> = data.iter().map(|x| x[j]).collect();
+                    Statistics::compute(&x, sampling_rate, n_fft)
+                })
+                .collect();
+            groups.push(GroupReport {
+                name: name.to_owned(),
+                n_sample: data.len(),
+                components,
+            });
+        }
+        if groups.is_empty() {
+            return Err(DOSError::Component(WindLoadsError::Empty));
+        }
+        Ok(WindLoadsReport {
+            sampling_rate,
+            duration: n_time as f64 * dt,
+            frequency: (0..=n_fft / 2)
+                .map(|k| k as f64 * sampling_rate / n_fft as f64)
+                .collect(),
+            groups,
+            total_force: total_force
+                .par_iter()
+                .map(|x| Statistics::compute(x, sampling_rate, n_fft))
+                .collect(),
+            issues,
+        })
+    }
+}
+
+impl WindLoadsReport {
+    /// Returns true if no problems have been found in the data
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+    /// Writes the report to a pickle file if the extension is `pkl` otherwise to a text file
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let mut f = File::create(path)?;
+        match path.extension().and_then(|x| x.to_str()) {
+            Some("pkl") => pkl::to_writer(&mut f, self, true)?,
+            _ => write!(f, "{}", self)?,
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for WindLoadsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "WIND LOADS: {:.0}Hz, {:.3}s",
+            self.sampling_rate, self.duration
+        )?;
+        let stats = |f: &mut fmt::Formatter<'_>, k: usize, s: &Statistics| {
+            writeln!(
+                f,
+                "  {:>3} {:>+12.3e} {:>12.3e} {:>12.3e}",
+                k, s.mean, s.rms, s.peak
+            )
+        };
+        for group in &self.groups {
+            writeln!(f, "{} ({} samples)", group.name, group.n_sample)?;
+            writeln!(f, "  {:>3} {:>12} {:>12} {:>12}", "#", "mean", "rms", "peak")?;
+            for (k, s) in group.components.iter().enumerate() {
+                stats(f, k, s)?;
+            }
+        }
+        writeln!(f, "TOTAL FORCE")?;
+        for (k, s) in self.total_force.iter().enumerate() {
+            stats(f, k, s)?;
+        }
+        if self.is_valid() {
+            writeln!(f, "NO ISSUES")
+        } else {
+            writeln!(f, "ISSUES")?;
+            self.issues
+                .iter()
+                .try_for_each(|issue| writeln!(f, "  {}", issue))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wind_loads::Loads;
+
+    #[test]
+    fn wind_loads_report() {
+        let sampling_rate = 100f64;
+        let time: Vec<f64> = (0..1000).map(|k| k as f64 / sampling_rate).collect();
+        let truss: Vec<Vec<f64>> = time
+            .iter()
+            .map(|t| {
+                let mut x = vec![1f64; 6];
+                x[0] += 2. * (2. * PI * 12.5 * t).sin();
+                x
+            })
+            .collect();
+        let mut gir = vec![vec![1f64; 6]; 999];
+        gir[10][3] = f64::NAN;
+        let report = WindLoads::new(
+            time,
+            vec![Loads::OSSTruss6F(truss), Loads::OSSGIR6F(gir)],
+        )
+        .report(256)
+        .unwrap();
+        let x = &report.groups[0].components[0];
+        assert!((x.mean - 1.).abs() < 1e-9);
+        assert!((x.rms - 3f64.sqrt()).abs() < 1e-9);
+        assert!((x.peak - 3.).abs() < 1e-6);
+        let k = x.psd[1..]
+            .iter()
+            .enumerate()
+            .fold((0, 0f64), |m, (k, p)| if *p > m.1 { (k + 1, *p) } else { m })
+            .0;
+        assert_eq!(report.frequency[k], 12.5);
+        let df = report.frequency[1];
+        // the mean leaks into the first bin with the Hann window
+        let var: f64 = x.psd[2..].iter().sum::<f64>() * df;
+        assert!((var - 2.).abs() < 0.05, "variance: {}", var);
+        assert_eq!(report.issues.len(), 2);
+    }
+
+    #[test]
+    fn wind_loads_report_long_segments() {
+        let sampling_rate = 100f64;
+        let time: Vec<f64> = (0..1000).map(|k| k as f64 / sampling_rate).collect();
+        let truss: Vec<Vec<f64>> = time
+            .iter()
+            .map(|t| {
+                let mut x = vec![0f64; 6];
+                x[0] = (2. * PI * 12.5 * t).sin();
+                x
+            })
+            .collect();
+        let loads = WindLoads::new(time, vec![Loads::OSSTruss6F(truss)]);
+        let report = loads.report(4096).unwrap();
+        assert_eq!(report.frequency.len(), 512 / 2 + 1);
+        assert!(report.groups[0].components[0].psd.iter().any(|p| *p > 0.));
+        assert!(WindLoads::new(vec![0.], vec![Loads::OSSTruss6F(vec![vec![0f64; 6]])])
+            .report(4096)
+            .is_err());
+    }
+}
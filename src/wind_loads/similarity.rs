@@ -0,0 +1,110 @@
+//! This module is used to derive wind loads at other wind conditions from an existing CFD case
+//!
+//! The forces and moments are scaled by the ratio of the dynamic pressures $`q=\rho U^2/2`$,
+//! where $`\rho`$ is the air density and $`U`$ is the wind speed:
+//! $$F' = {\rho' U'^2 \over \rho U^2} F$$
+//! The time vector is scaled according to the Strouhal similarity, the Strouhal number $`fL/U`$ being constant:
+//! $$t' = {U \over U'} t$$
+//! As the sampling rate of the derived wind loads differs from the sampling rate of the CFD case,
+//! the wind loads must be resampled (see [`WindLoads::resample`]) at the sampling rate of the simulation.
+//!
+//! # Example
+//! Deriving the wind loads at 10m/s from the 7m/s CFD case
+//! ```no_run
+//! use dos::wind_loads::{Interpolation, WindConditions, WindLoads};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let cfd = WindConditions::new(7.0);
+//!     let wind_loading = WindLoads::from_pickle("b2019_0z_0az_os_7ms.pkl")?
+//!         .similarity(cfd, cfd.wind_speed(10.0))
+//!         .resample(1e3, Interpolation::BandLimited)
+//!         .select_all()?
+//!         .build()?;
+//!     Ok(())
+//! }
+//! ```
+
+use super::WindLoads;
+
+/// Default air density in kg/m<sup>3</sup>
+///
+/// Only the ratio of air densities matters, so the default air density can be used for both the CFD and the target wind conditions when the air density does not change
+pub const AIR_DENSITY: f64 = 1.0;
+
+/// Wind conditions of a wind loads case
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindConditions {
+    /// Wind speed in m/s
+    pub wind_speed: f64,
+    /// Air density in kg/m<sup>3</sup>
+    pub air_density: f64,
+}
+impl WindConditions {
+    /// Creates new wind conditions with the wind speed `wind_speed` and the default air density [`AIR_DENSITY`]
+    pub fn new(wind_speed: f64) -> Self {
+        Self {
+            wind_speed,
+            air_density: AIR_DENSITY,
+        }
+    }
+    /// Sets the wind speed in m/s
+    pub fn wind_speed(self, wind_speed: f64) -> Self {
+        Self { wind_speed, ..self }
+    }
+    /// Sets the air density in kg/m<sup>3</sup>
+    pub fn air_density(self, air_density: f64) -> Self {
+        Self {
+            air_density,
+            ..self
+        }
+    }
+    /// Returns the dynamic pressure in Pa
+    pub fn dynamic_pressure(&self) -> f64 {
+        0.5 * self.air_density * self.wind_speed * self.wind_speed
+    }
+}
+
+impl WindLoads {
+    /// Multiplies all the forces and moments by `factor`
+    pub fn scale(mut self, factor: f64) -> Self {
+        self.loads
+            .iter_mut()
+            .filter_map(|x| x.as_mut())
+            .for_each(|x| x.scale(factor));
+        self
+    }
+    /// Multiplies the time vector by `factor`
+    pub fn time_scale(mut self, factor: f64) -> Self {
+        self.time.iter_mut().for_each(|t| *t *= factor);
+        self
+    }
+    /// Derives the wind loads at the `target` wind conditions from the wind loads at the `cfd` wind conditions
+    ///
+    /// The forces and moments are scaled by the ratio of the dynamic pressures and the time vector by the ratio of the wind speeds
+    pub fn similarity(self, cfd: WindConditions, target: WindConditions) -> Self {
+        self.scale(target.dynamic_pressure() / cfd.dynamic_pressure())
+            .time_scale(cfd.wind_speed / target.wind_speed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wind_loads::Loads;
+
+    #[test]
+    fn similarity_scaling() {
+        let wind_loads = WindLoads::new(
+            vec![0., 0.5, 1.],
+            vec![Loads::OSSGIR6F(vec![vec![1., -2.]; 3])],
+        );
+        let cfd = WindConditions::new(7.0);
+        let target = cfd.wind_speed(14.0).air_density(2.0);
+        let scaled = wind_loads.similarity(cfd, target);
+        assert_eq!(scaled.time, vec![0., 0.25, 0.5]);
+        assert_eq!(
+            scaled.loads[0].as_ref().unwrap().as_slice(),
+            &[vec![8., -16.], vec![8., -16.], vec![8., -16.]]
+        );
+    }
+}
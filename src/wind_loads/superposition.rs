@@ -0,0 +1,204 @@
+//! This module is used to superpose several wind loads records
+//!
+//! Records such as the CFD wind loads and a separately computed vortex-shedding or seismic record are combined
+//! either before they are applied to the FEM inputs with [`WindLoads::superpose`] or afterwards with [`WindLoading::superpose`].
+//! The loads applied to the same load group or to the same FEM input are summed sample by sample;
+//! the time vectors, the number of samples and the sizes of the loads must agree.
+//!
+//! # Example
+//! ```no_run
+//! use dos::wind_loads::WindLoads;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let vortex_shedding = WindLoads::from_file("vortex_shedding.npz")?
+//!         .truss()?
+//!         .build()?;
+//!     let wind_loading = WindLoads::from_pickle("b2019_0z_0az_os_7ms.pkl")?
+//!         .range(0.0, 400.0)?
+//!         .select_all()?
+//!         .build()?
+//!         .superpose(vortex_shedding)?;
+//!     Ok(())
+//! }
+//! ```
+
+use super::{Loads, Result, WindLoading, WindLoads, WindLoadsError};
+use crate::{io::Tags, DOSError, IO};
+
+/// Maximum difference in seconds between the time samples of superposed records
+pub const TIME_TOLERANCE: f64 = 1e-6;
+
+type Tagged = IO<std::vec::IntoIter<Vec<f64>>>;
+
+fn mismatch<T>(msg: String) -> Result<T> {
+    Err(DOSError::Component(WindLoadsError::Mismatch(msg)))
+}
+/// Checks that both time vectors are the same within [`TIME_TOLERANCE`]
+fn same_time(time: &[f64], other: &[f64]) -> Result<()> {
+    if time.len() != other.len()
+        || time
+            .iter()
+            .zip(other.iter())
+            .any(|(t, o)| (t - o).abs() > TIME_TOLERANCE)
+    {
+        return mismatch("wind loads time vectors differ".to_owned());
+    }
+    Ok(())
+}
+/// Sums two time series sample by sample
+fn sum(name: &str, x: Vec<Vec<f64>>, y: Vec<Vec<f64>>) -> Result<Vec<Vec<f64>>> {
+    if x.len() != y.len() {
+        return mismatch(format!(
+            "{}: {} and {} samples",
+            name,
+            x.len(),
+            y.len()
+        ));
+    }
+    x.into_iter()
+        .zip(y)
+        .map(|(x, y)| {
+            if x.len() != y.len() {
+                mismatch(format!("{}: sizes {} and {}", name, x.len(), y.len()))
+            } else {
+                Ok(x.into_iter().zip(y).map(|(x, y)| x + y).collect())
+            }
+        })
+        .collect()
+}
+/// Sums the `IO` time series of `other` into the `IO` time series of `this` with the same tags
+fn sum_tagged(this: &mut Vec<Tagged>, other: Vec<Tagged>) -> Result<()> {
+    for io in other {
+        let tag: Tags = (&io).into();
+        match this.iter_mut().find(|x| **x == tag) {
+            Some(x) => {
+                let data = sum(
+                    &format!("{:?}", tag),
+                    Option::<std::vec::IntoIter<Vec<f64>>>::from(&*x)
+                        .map_or(vec![], |x| x.collect()),
+                    Option::<std::vec::IntoIter<Vec<f64>>>::from(io)
+                        .map_or(vec![], |x| x.collect()),
+                )?;
+                *x = (&tag, Some(data.into_iter())).into();
+            }
+            None => this.push(io),
+        }
+    }
+    Ok(())
+}
+
+impl WindLoads {
+    /// Adds the wind loads of `other` to the wind loads
+    ///
+    /// The loads of the same load group and the loads already applied to the same FEM input are summed, the others are appended.
+    /// Both time vectors must be the same within [`TIME_TOLERANCE`]; the settings, like the number of samples, are the ones of `self`
+    pub fn superpose(mut self, other: WindLoads) -> Result<Self> {
+        same_time(&self.time, &other.time)?;
+        for loads in other.loads.into_iter().flatten() {
+            let name = loads.name();
+            match self
+                .loads
+                .iter_mut()
+                .find(|x| matches!(x, Some(x) if x.name() == name))
+            {
+                Some(x) => {
+                    let data = sum(name, x.take().unwrap().io(), loads.io())?;
+                    *x = Loads::from_name(name, data);
+                }
+                None => self.loads.push(Some(loads)),
+            }
+        }
+        sum_tagged(&mut self.tagged_loads, other.tagged_loads)?;
+        Ok(self)
+    }
+}
+
+impl WindLoading {
+    /// Adds the wind loading of `other` to the wind loading
+    ///
+    /// The loads applied to the same FEM input are summed, the others are appended.
+    /// Both wind loadings must have the same time vectors within [`TIME_TOLERANCE`], number of samples, ramp-in, offset and looping and must not have been stepped through.
+    /// The wind loading can be reset only if both wind loadings can be reset
+    pub fn superpose(mut self, other: WindLoading) -> Result<Self> {
+        if self.step > 0 || other.step > 0 {
+            return mismatch("wind loading has already been stepped through".to_owned());
+        }
+        if self.n_sample != other.n_sample {
+            return mismatch(format!(
+                "wind loading with {} and {} samples",
+                self.n_sample, other.n_sample
+            ));
+        }
+        same_time(&self.time, &other.time)?;
+        if self.ramp_in != other.ramp_in || self.offset != other.offset {
+            return mismatch("wind loading with different ramp-in or offset".to_owned());
+        }
+        if self.looping != other.looping {
+            return mismatch("looping and non-looping wind loading".to_owned());
+        }
+        self.record = match (self.record, other.record) {
+            (Some(mut record), Some(other_record)) => {
+                sum_tagged(&mut record, other_record)?;
+                Some(record)
+            }
+            _ => None,
+        };
+        sum_tagged(&mut self.loads, other.loads)?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{io::jar, DOS};
+
+    #[test]
+    fn superpose_wind_loading() {
+        let time: Vec<f64> = (0..4).map(|k| k as f64).collect();
+        let cfd = WindLoads::new(
+            time.clone(),
+            vec![
+                Loads::OSSTruss6F(vec![vec![1f64; 6]; 4]),
+                Loads::OSSGIR6F(vec![vec![1f64; 6]; 4]),
+            ],
+        );
+        let seismic = WindLoads::new(time, vec![Loads::OSSTruss6F(vec![vec![2f64; 6]; 4])]);
+        let mut wind_loading = cfd
+            .truss()
+            .unwrap()
+            .gir()
+            .unwrap()
+            .build()
+            .unwrap()
+            .superpose(seismic.truss().unwrap().build().unwrap())
+            .unwrap();
+        let outputs = wind_loading.outputs().unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(
+            Option::<Vec<f64>>::from(&outputs[jar::OSSTruss6F::new()]).unwrap(),
+            vec![3f64; 6]
+        );
+        let short = WindLoads::new(
+            vec![0., 1.],
+            vec![Loads::OSSTruss6F(vec![vec![2f64; 6]; 2])],
+        );
+        assert!(WindLoads::new(vec![0., 1., 2.], vec![])
+            .superpose(short)
+            .is_err());
+        let slow = WindLoads::new(
+            (0..4).map(|k| 2. * k as f64).collect(),
+            vec![Loads::OSSTruss6F(vec![vec![2f64; 6]; 4])],
+        );
+        assert!(WindLoads::new(
+            (0..4).map(|k| k as f64).collect(),
+            vec![Loads::OSSGIR6F(vec![vec![1f64; 6]; 4])]
+        )
+        .gir()
+        .unwrap()
+        .build()
+        .unwrap()
+        .superpose(slow.truss().unwrap().build().unwrap())
+        .is_err());
+    }
+}
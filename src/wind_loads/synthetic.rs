@@ -0,0 +1,457 @@
+//! This module is used to generate stochastic wind loads when no CFD case is available
+//!
+//! The time series of each load group are synthesized in the frequency domain from a power spectral density (PSD) and a coherence between the components of the load group.
+//! For the $`j`$th component of a load group with the standard deviation $`\sigma_j`$, the Fourier coefficient at the frequency $`f_k`$ is
+//! $$X_j(f_k) = \sigma_j\sqrt{S(f_k)\Delta f/2}\left(\sqrt{\gamma(f_k)}z_k + \sqrt{1-\gamma(f_k)}z_{jk}\right)$$
+//! where $`S`$ is the normalized one-sided [`Spectrum`], $`\gamma`$ is the magnitude-squared [`Coherence`] and $`z_k`$ and $`z_{jk}`$ are unit complex Gaussian random variables, $`z_k`$ being common to all the components.
+//! The time series are then obtained by inverse Fourier transform.
+//!
+//! The synthetic loads are either returned as [`WindLoads`] with [`SyntheticWindLoads::build`], and are then selected and built into a [`WindLoading`](crate::wind_loads::WindLoading) source the same way than the CFD wind loads,
+//! or returned as the [`SyntheticWindLoading`] source with [`SyntheticWindLoads::source`], each load group being applied to the FEM input given by [`SyntheticLoads::input`].
+//!
+//! # Example
+//! ```no_run
+//! use dos::{
+//!     io::jar,
+//!     wind_loads::synthetic::{Spectrum, SyntheticLoads, SyntheticWindLoads, Coherence},
+//! };
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut wind_loading = SyntheticWindLoads::new(1e3, 400.0)
+//!         .seed(2021)
+//!         .loads(
+//!             SyntheticLoads::new(
+//!                 jar::OSSTopEnd6F::new(),
+//!                 Spectrum::VonKarman {
+//!                     length_scale: 10.0,
+//!                     wind_speed: 7.0,
+//!                 },
+//!                 vec![100.0; 6],
+//!             )
+//!             .coherence(Coherence::Exponential { decay: 0.5 }),
+//!         )
+//!         .build()?
+//!         .topend()?
+//!         .build()?;
+//!     Ok(())
+//! }
+//! ```
+//! or, with the synthetic source, the wind loads on the M2 segments applied to the ASM reference bodies
+//! ```no_run
+//! use dos::{
+//!     io::jar,
+//!     wind_loads::synthetic::{Spectrum, SyntheticLoads, SyntheticWindLoads},
+//!     DOS,
+//! };
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut wind_loading = SyntheticWindLoads::new(1e3, 400.0)
+//!         .loads(
+//!             SyntheticLoads::new(
+//!                 jar::MCM2Lcl6F::new(),
+//!                 Spectrum::VonKarman {
+//!                     length_scale: 10.0,
+//!                     wind_speed: 7.0,
+//!                 },
+//!                 vec![10.0; 42],
+//!             )
+//!             .input(jar::MCM2RB6F::new()),
+//!         )
+//!         .source()?;
+//!     while let Some(fem_forces) = wind_loading.outputs() {
+//!         // ...
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use super::{Loads, Result, WindLoads, WindLoadsError};
+use crate::{
+    io::{Tags, IO},
+    DOSError, IOTags, DOS,
+};
+use num_complex::Complex;
+use rand::{rngs::StdRng, SeedableRng};
+use rand_distr::{Distribution, Normal};
+use rayon::prelude::*;
+use std::f64::consts::PI;
+
+/// Normalized one-sided power spectral density
+///
+/// The PSD of the $`j`$th component of a load group is the spectrum multiplied by the component variance $`\sigma_j^2`$
+#[derive(Debug, Clone)]
+pub enum Spectrum {
+    /// von Kármán spectrum with the integral length scale `length_scale` in m and the mean wind speed `wind_speed` in m/s
+    ///
+    /// $$S(f) = {4L/U \over \left(1+70.8(fL/U)^2\right)^{5/6}}$$
+    /// The spectrum has a unit variance.
+    VonKarman { length_scale: f64, wind_speed: f64 },
+    /// PSD table of `(frequency [Hz], PSD)`, linearly interpolated and set to 0 outside the table frequency range
+    Table(Vec<(f64, f64)>),
+}
+impl Spectrum {
+    /// Returns the spectrum at the frequency `f` in Hz
+    pub fn psd(&self, f: f64) -> f64 {
+        match self {
+            Spectrum::VonKarman {
+                length_scale,
+                wind_speed,
+            } => {
+                let t = length_scale / wind_speed;
+                4. * t / (1. + 70.8 * (f * t).powi(2)).powf(5. / 6.)
+            }
+            Spectrum::Table(table) => interpolate(table, f),
+        }
+    }
+}
+
+/// Magnitude-squared coherence between the components of a load group
+#[derive(Debug, Clone)]
+pub enum Coherence {
+    /// Frequency independent coherence, 0 for independent components and 1 for fully correlated components
+    Constant(f64),
+    /// Coherence decreasing exponentially with the frequency: $`\gamma(f)=\exp(-cf)`$ with the decay `c` in s
+    Exponential { decay: f64 },
+    /// Coherence table of `(frequency [Hz], coherence)`, linearly interpolated and set to 0 outside the table frequency range
+    Table(Vec<(f64, f64)>),
+}
+impl Coherence {
+    /// Returns the coherence at the frequency `f` in Hz
+    pub fn gamma(&self, f: f64) -> f64 {
+        let gamma = match self {
+            Coherence::Constant(gamma) => *gamma,
+            Coherence::Exponential { decay } => (-decay * f).exp(),
+            Coherence::Table(table) => interpolate(table, f),
+        };
+        gamma.clamp(0f64, 1f64)
+    }
+}
+
+fn interpolate(table: &[(f64, f64)], f: f64) -> f64 {
+    table
+        .windows(2)
+        .find(|x| x[0].0 <= f && f <= x[1].0)
+        .map_or(0f64, |x| {
+            let (f0, y0) = x[0];
+            let (f1, y1) = x[1];
+            if f1 > f0 {
+                y0 + (y1 - y0) * (f - f0) / (f1 - f0)
+            } else {
+                y0
+            }
+        })
+}
+
+/// Synthetic load group
+pub struct SyntheticLoads {
+    group: Tags,
+    spectrum: Spectrum,
+    std: Vec<f64>,
+    mean: Option<Vec<f64>>,
+    coherence: Coherence,
+    input: Option<Tags>,
+}
+impl SyntheticLoads {
+    /// Creates a new synthetic load group from the CFD load group `group`, the `spectrum` and the standard deviation `std` of each component
+    ///
+    /// The components are independent and with a zero mean
+    pub fn new(group: Tags, spectrum: Spectrum, std: Vec<f64>) -> Self {
+        Self {
+            group,
+            spectrum,
+            std,
+            mean: None,
+            coherence: Coherence::Constant(0f64),
+            input: None,
+        }
+    }
+    /// Sets the mean of each component
+    pub fn mean(self, mean: Vec<f64>) -> Self {
+        Self {
+            mean: Some(mean),
+            ..self
+        }
+    }
+    /// Sets the coherence between the components
+    pub fn coherence(self, coherence: Coherence) -> Self {
+        Self { coherence, ..self }
+    }
+    /// Sets the FEM input the loads are applied to by the [`SyntheticWindLoading`] source, the load group by default
+    pub fn input(self, input: Tags) -> Self {
+        Self {
+            input: Some(input),
+            ..self
+        }
+    }
+}
+
+/// Synthetic wind loads builder
+pub struct SyntheticWindLoads {
+    sampling_rate: f64,
+    duration: f64,
+    seed: u64,
+    loads: Vec<SyntheticLoads>,
+}
+impl SyntheticWindLoads {
+    /// Creates a new synthetic wind loads builder with the `sampling_rate` in Hz and the `duration` in s
+    pub fn new(sampling_rate: f64, duration: f64) -> Self {
+        Self {
+            sampling_rate,
+            duration,
+            seed: 0,
+            loads: vec![],
+        }
+    }
+    /// Sets the random generator seed
+    pub fn seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+    /// Adds a synthetic load group
+    pub fn loads(self, loads: SyntheticLoads) -> Self {
+        let mut this = self;
+        this.loads.push(loads);
+        this
+    }
+    /// Synthesizes the wind loads
+    pub fn build(self) -> Result<WindLoads> {
+        let (time, loads) = self.synthesize()?;
+        Ok(WindLoads::new(
+            time,
+            loads
+                .into_iter()
+                .filter_map(|(name, _, io)| Loads::from_name(name, io))
+                .collect(),
+        ))
+    }
+    /// Synthesizes the wind loads into a wind loads source
+    pub fn source(self) -> Result<SyntheticWindLoading> {
+        let sampling_rate = self.sampling_rate;
+        let (time, loads) = self.synthesize()?;
+        Ok(SyntheticWindLoading {
+            sampling_rate,
+            n_sample: time.len(),
+            step: 0,
+            loads: loads.into_iter().map(|(_, input, io)| (input, io)).collect(),
+        })
+    }
+    // Returns the time vector and, for each load group, the group name, the FEM input and the time series
+    #[allow(clippy::type_complexity)]
+    fn synthesize(self) -> Result<(Vec<f64>, Vec<(&'static str, Tags, Vec<Vec<f64>>)>)> {
+        let n_sample = (self.duration * self.sampling_rate).round() as usize;
+        let n_fft = n_sample.max(2).next_power_of_two();
+        let df = self.sampling_rate / n_fft as f64;
+        let time: Vec<f64> = (0..n_sample)
+            .map(|k| k as f64 / self.sampling_rate)
+            .collect();
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let normal = Normal::new(0f64, 0.5f64.sqrt()).unwrap();
+        let mut z = || Complex::new(normal.sample(&mut rng), normal.sample(&mut rng));
+        let mut loads = vec![];
+        for synthetic in self.loads {
+            let name = Loads::name_of(&synthetic.group).ok_or_else(|| {
+                DOSError::Component(WindLoadsError::Missing(format!("{:?}", synthetic.group)))
+            })?;
+            let n = synthetic.std.len();
+            let mean = synthetic.mean.unwrap_or_else(|| vec![0f64; n]);
+            if mean.len() != n {
+                return Err(DOSError::Component(WindLoadsError::Mismatch(format!(
+                    "{} mean and std have different sizes",
+                    name
+                ))));
+            }
+            // Fourier coefficients from 1 to n_fft/2-1
+            let spectra: Vec<Vec<Complex<f64>>> = {
+                let mut spectra = vec![Vec::with_capacity(n_fft / 2); n];
+                for k in 1..n_fft / 2 {
+                    let f = k as f64 * df;
+                    let a = (synthetic.spectrum.psd(f) * df * 0.5).sqrt();
+                    let gamma = synthetic.coherence.gamma(f);
+                    let z_common = z() * gamma.sqrt();
+                    for spectrum in spectra.iter_mut() {
+                        spectrum.push((z_common + z() * (1. - gamma).sqrt()) * a);
+                    }
+                }
+                spectra
+            };
+            let series: Vec<Vec<f64>> = spectra
+                .into_par_iter()
+                .zip(synthetic.std.par_iter().zip(mean.par_iter()))
+                .map(|(spectrum, (std, mean))| {
+                    let mut x = vec![Complex::new(0f64, 0f64); n_fft];
+                    for (k, c) in spectrum.into_iter().enumerate() {
+                        x[k + 1] = c;
+                        x[n_fft - k - 1] = c.conj();
+                    }
+                    inverse_fft(&mut x);
+                    x.into_iter()
+                        .take(n_sample)
+                        .map(|x| mean + std * x.re)
+                        .collect()
+                })
+                .collect();
+            let io: Vec<Vec<f64>> = (0..n_sample)
+                .map(|i| series.iter().map(|x| x[i]).collect())
+                .collect();
+            let input = synthetic.input.unwrap_or(synthetic.group);
+            loads.push((name, input, io));
+        }
+        Ok((time, loads))
+    }
+}
+
+/// Synthetic wind loads source
+///
+/// The source outputs one sample of each load group per call to [`outputs`](crate::DOS::outputs) or to [`next`](core::iter::Iterator::next) until the end of the time series
+pub struct SyntheticWindLoading {
+    pub sampling_rate: f64,
+    pub n_sample: usize,
+    step: usize,
+    loads: Vec<(Tags, Vec<Vec<f64>>)>,
+}
+impl SyntheticWindLoading {
+    /// Returns the current time index
+    pub fn step(&self) -> usize {
+        self.step
+    }
+}
+impl Iterator for SyntheticWindLoading {
+    type Item = Vec<IO<Vec<f64>>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.outputs()
+    }
+}
+impl IOTags for SyntheticWindLoading {
+    fn outputs_tags(&self) -> Vec<Tags> {
+        self.loads.iter().map(|(tag, _)| tag.clone()).collect()
+    }
+    fn inputs_tags(&self) -> Vec<Tags> {
+        vec![]
+    }
+}
+impl DOS for SyntheticWindLoading {
+    fn inputs(
+        &mut self,
+        _: Vec<IO<Vec<f64>>>,
+    ) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        Err("SyntheticWindLoading takes no inputs".into())
+    }
+    /// Restarts the loads from the first sample
+    fn reset(&mut self) -> std::result::Result<&mut Self, Box<dyn std::error::Error>> {
+        self.step = 0;
+        Ok(self)
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        if self.step >= self.n_sample {
+            return None;
+        }
+        let k = self.step;
+        self.step += 1;
+        Some(
+            self.loads
+                .iter()
+                .map(|(tag, io)| IO::<Vec<f64>>::from((tag, io[k].clone())))
+                .collect(),
+        )
+    }
+}
+
+/// In-place unnormalized inverse FFT, the length of `x` must be a power of 2
+pub(super) fn inverse_fft(x: &mut [Complex<f64>]) {
+    let n = x.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            x.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let w = Complex::from_polar(1f64, 2. * PI / len as f64);
+        for chunk in x.chunks_mut(len) {
+            let mut wk = Complex::new(1f64, 0f64);
+            let (a, b) = chunk.split_at_mut(len / 2);
+            for (a, b) in a.iter_mut().zip(b.iter_mut()) {
+                let t = *b * wk;
+                *b = *a - t;
+                *a += t;
+                wk *= w;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::jar;
+
+    #[test]
+    fn synthetic_variance_and_coherence() {
+        let build = || {
+            SyntheticWindLoads::new(1e3, 100.0)
+                .seed(7)
+                .loads(
+                    SyntheticLoads::new(
+                        jar::OSSTopEnd6F::new(),
+                        Spectrum::VonKarman {
+                            length_scale: 1.0,
+                            wind_speed: 10.0,
+                        },
+                        vec![2f64; 6],
+                    )
+                    .mean(vec![1f64; 6])
+                    .coherence(Coherence::Constant(1f64)),
+                )
+                .build()
+                .unwrap()
+        };
+        let loads = build().loads.remove(0).unwrap().io();
+        assert_eq!(loads.len(), 100_000);
+        let n = loads.len() as f64;
+        let mean = loads.iter().map(|x| x[0]).sum::<f64>() / n;
+        let var = loads.iter().map(|x| (x[0] - mean).powi(2)).sum::<f64>() / n;
+        assert!((mean - 1.).abs() < 0.1, "mean: {}", mean);
+        assert!((var - 4.).abs() < 0.4, "variance: {}", var);
+        assert!(loads.iter().all(|x| (x[0] - x[5]).abs() < 1e-9));
+        assert_eq!(loads, build().loads.remove(0).unwrap().io());
+    }
+
+    #[test]
+    fn synthetic_source_variance() {
+        // flat unit variance spectrum from 0 to 50Hz
+        let spectrum = Spectrum::Table(vec![(0., 0.02), (50., 0.02)]);
+        let mut source = SyntheticWindLoads::new(1e3, 100.0)
+            .seed(11)
+            .loads(
+                SyntheticLoads::new(jar::MCM2Lcl6F::new(), spectrum.clone(), vec![3f64; 42])
+                    .input(jar::MCM2RB6F::new()),
+            )
+            .source()
+            .unwrap();
+        assert_eq!(source.outputs_tags(), vec![jar::MCM2RB6F::new::<()>()]);
+        let first = source.outputs().unwrap();
+        let mut x: Vec<f64> = vec![Option::<Vec<f64>>::from(&first[0]).unwrap()[7]];
+        x.extend(source.by_ref().map(|y| Option::<Vec<f64>>::from(&y[0]).unwrap()[7]));
+        assert_eq!(x.len(), 100_000);
+        assert!(source.outputs().is_none());
+        let n = x.len() as f64;
+        let mean = x.iter().sum::<f64>() / n;
+        let var = x.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        // the variance of the component is its variance times the spectrum integral
+        let df = 0.01;
+        let integral = (0..5_000).map(|k| spectrum.psd(k as f64 * df) * df).sum::<f64>();
+        assert!((var - 9. * integral).abs() < 0.05 * 9., "variance: {}", var);
+        source.reset().unwrap();
+        assert_eq!(
+            Option::<Vec<f64>>::from(&source.outputs().unwrap()[0]),
+            Option::<Vec<f64>>::from(&first[0])
+        );
+    }
+}